@@ -12,8 +12,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Generate random workspace coordinates
     let mut rng = rand::rng();
-    let target_x: i32 = rng.random_range(0..3);
-    let target_y: i32 = rng.random_range(0..3);
+    let target_x: i64 = rng.random_range(0..3);
+    let target_y: i64 = rng.random_range(0..3);
 
     println!(
         "Moving view {} to workspace ({}, {})",
@@ -21,7 +21,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     );
 
     // Send the view to the random workspace
-    match socket.send_view_to_workspace(view_id as i32, target_x, target_y).await {
+    match socket.send_view_to_workspace(view_id, target_x, target_y).await {
         Ok(_) => println!("Sent view to workspace successfully."),
         Err(e) => eprintln!("Failed to send view to workspace: {}", e),
     }
@@ -15,11 +15,11 @@ async fn main() -> Result<(), Box<dyn Error>> {
     let mut socket = WayfireSocket::connect().await?;
 
     // Toggle the expo effect twice 
-    match socket.expo_toggle().await {
+    match socket.expo_toggle(None).await {
         Ok(view_alpha) => print_json("toggle expo", view_alpha).await?,
         Err(e) => eprintln!("Failed to toggle expo: {}", e),
     }
-    match socket.expo_toggle().await {
+    match socket.expo_toggle(None).await {
         Ok(view_alpha) => print_json("toggle expo", view_alpha).await?,
         Err(e) => eprintln!("Failed to toggle expo: {}", e),
     }
@@ -35,19 +35,19 @@ async fn main() -> Result<(), Box<dyn Error>> {
     }
 
     // Activate the cube effect
-    match socket.cube_activate().await {
+    match socket.cube_activate(None).await {
         Ok(_) => println!("Cube activated successfully."),
         Err(e) => eprintln!("Failed to activate cube: {}", e),
     }
 
     // Rotate the cube to the left 
-    match socket.cube_rotate_left().await {
+    match socket.cube_rotate_left(None).await {
         Ok(_) => println!("Cube rotated left successfully."),
         Err(e) => eprintln!("Failed to rotate cube left: {}", e),
     }
 
     // Rotate the cube to the right 
-    match socket.cube_rotate_right().await {
+    match socket.cube_rotate_right(None).await {
         Ok(_) => println!("Cube rotated right successfully."),
         Err(e) => eprintln!("Failed to rotate cube right: {}", e),
     }
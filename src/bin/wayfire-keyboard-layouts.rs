@@ -0,0 +1,54 @@
+use std::env;
+use std::error::Error;
+use wayfire_rs::ipc::WayfireSocket;
+use wayfire_rs::keyboard::{LayoutEvent, LayoutMemory, LayoutScope};
+
+const USAGE: &str = "Usage: wayfire-keyboard-layouts [--per-app] [--default INDEX]
+
+Remembers the keyboard layout of each window and switches back to it whenever
+the window is focused.
+
+  --per-app        Share one layout between the windows of an app
+  --default INDEX  Layout for windows that haven't been focused before";
+
+#[pollster::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let mut args = env::args().skip(1);
+    let mut scope = LayoutScope::View;
+    let mut default_layout = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--per-app" => scope = LayoutScope::App,
+            "--default" => {
+                let index: u32 = args
+                    .next()
+                    .and_then(|s| s.parse().ok())
+                    .ok_or("--default expects a layout index")?;
+                default_layout = Some(index);
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            _ => {
+                eprintln!("Unknown argument: {}\n\n{}", arg, USAGE);
+                std::process::exit(2);
+            }
+        }
+    }
+
+    let mut memory = LayoutMemory::new(scope);
+    if let Some(index) = default_layout {
+        memory = memory.default_layout(index);
+    }
+
+    let mut socket = WayfireSocket::connect().await?;
+    memory
+        .run(&mut socket, |event| match event {
+            LayoutEvent::Switched(index) => println!("Switched to layout {}", index),
+            LayoutEvent::Failed(e) => eprintln!("Failed to switch keyboard layout: {}", e),
+        })
+        .await?;
+    Ok(())
+}
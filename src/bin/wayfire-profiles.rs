@@ -0,0 +1,80 @@
+use std::env;
+use std::error::Error;
+use std::path::PathBuf;
+use wayfire_rs::ini::default_config_path;
+use wayfire_rs::ipc::WayfireSocket;
+use wayfire_rs::profiles::{ProfileConfig, ProfileDaemon, ProfileUpdate};
+
+const USAGE: &str = "Usage: wayfire-profiles [--dry-run] [--once] [CONFIG]
+
+Applies output profiles from CONFIG (default: $XDG_CONFIG_HOME/wayfire-rs/profiles.toml)
+whenever outputs are connected or disconnected.
+
+  --dry-run  Print the actions of the matching profile without applying them
+  --once     Apply the matching profile once and exit";
+
+fn print_update(update: ProfileUpdate, dry_run: bool) {
+    match update {
+        ProfileUpdate::NoMatch(outputs) => {
+            println!("No profile matches outputs: {}", outputs.join(", "))
+        }
+        ProfileUpdate::Unchanged(_) => {}
+        ProfileUpdate::Applied { profile, actions } => {
+            let verb = if dry_run { "Would apply" } else { "Applied" };
+            println!("{} profile '{}'", verb, profile);
+            for action in &actions {
+                println!("  {}", action);
+            }
+        }
+        ProfileUpdate::Failed {
+            profile, failures, ..
+        } => {
+            eprintln!("Failed to apply profile '{}'", profile);
+            for failure in &failures {
+                eprintln!("  {}: {}", failure.output, failure.error);
+            }
+        }
+    }
+}
+
+#[pollster::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let mut dry_run = false;
+    let mut once = false;
+    let mut config_path = None;
+
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--dry-run" => dry_run = true,
+            "--once" => once = true,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            _ if arg.starts_with('-') => {
+                eprintln!("Unknown option: {}\n\n{}", arg, USAGE);
+                std::process::exit(2);
+            }
+            _ => config_path = Some(PathBuf::from(arg)),
+        }
+    }
+
+    let config_path = config_path
+        .or_else(|| default_config_path("profiles.toml"))
+        .ok_or("Could not determine the config path, pass it explicitly")?;
+    let config = ProfileConfig::load(&config_path)
+        .map_err(|e| format!("{}: {}", config_path.display(), e))?;
+
+    let mut socket = WayfireSocket::connect().await?;
+    let mut daemon = ProfileDaemon::new(config, dry_run);
+
+    if once {
+        print_update(daemon.update(&mut socket).await?, dry_run);
+        return Ok(());
+    }
+
+    daemon
+        .run(&mut socket, |update| print_update(update, dry_run))
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,65 @@
+use std::env;
+use std::error::Error;
+use std::path::PathBuf;
+use wayfire_rs::ini::default_config_path;
+use wayfire_rs::ipc::WayfireSocket;
+use wayfire_rs::rules::{RuleEngine, RuleEvent};
+
+const USAGE: &str = "Usage: wayfire-rules [--check] [CONFIG]
+
+Applies window rules from CONFIG (default: $XDG_CONFIG_HOME/wayfire-rs/rules.toml)
+to views as they are mapped. YAML is used for files ending in .yaml or .yml.
+The file is reloaded automatically when it changes.
+
+  --check  Parse the rules, print them and exit";
+
+#[pollster::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let mut check = false;
+    let mut config_path = None;
+
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--check" => check = true,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            _ if arg.starts_with('-') => {
+                eprintln!("Unknown option: {}\n\n{}", arg, USAGE);
+                std::process::exit(2);
+            }
+            _ => config_path = Some(PathBuf::from(arg)),
+        }
+    }
+
+    let config_path = config_path
+        .or_else(|| default_config_path("rules.toml"))
+        .ok_or("Could not determine the config path, pass it explicitly")?;
+    let mut engine =
+        RuleEngine::load(&config_path).map_err(|e| format!("{}: {}", config_path.display(), e))?;
+
+    if check {
+        for rule in &engine.rules().rules {
+            println!("{}: {}", rule.display_name(), rule.matches);
+        }
+        return Ok(());
+    }
+
+    let mut socket = WayfireSocket::connect().await?;
+    engine
+        .run(&mut socket, |event| match event {
+            RuleEvent::Reloaded(count) => println!("Reloaded {} rules", count),
+            RuleEvent::ReloadFailed(e) => eprintln!("Failed to reload rules: {}", e),
+            RuleEvent::Applied { view, rule } => println!(
+                "Applied rule '{}' to view {} ({})",
+                rule, view.id, view.app_id
+            ),
+            RuleEvent::Failed { view, rule, error } => eprintln!(
+                "Failed to apply rule '{}' to view {} ({}): {}",
+                rule, view.id, view.app_id, error
+            ),
+        })
+        .await?;
+    Ok(())
+}
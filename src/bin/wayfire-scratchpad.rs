@@ -0,0 +1,68 @@
+use std::env;
+use std::error::Error;
+use wayfire_rs::ipc::WayfireSocket;
+use wayfire_rs::models::WorkspacePosition;
+use wayfire_rs::scratchpad::{HideMethod, Scratchpad, ScratchpadState};
+
+const USAGE: &str = "Usage: wayfire-scratchpad [OPTIONS] APP_ID [COMMAND]
+
+Shows the window with APP_ID centered on the focused output, or hides it if it
+is already shown. If no such window exists, COMMAND is run to start it.
+
+  --size WxH         Size relative to the work area (default: 0.6x0.6)
+  --workspace X,Y    Hide the window on this workspace instead of minimizing it";
+
+#[pollster::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let mut args = env::args().skip(1);
+    let mut positional = Vec::new();
+    let mut size = None;
+    let mut hide = HideMethod::Minimize;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--size" => {
+                let value = args.next().unwrap_or_default();
+                let (w, h) = value.split_once('x').ok_or("--size expects WxH")?;
+                size = Some((w.parse::<f64>()?, h.parse::<f64>()?));
+            }
+            "--workspace" => {
+                let value = args.next().unwrap_or_default();
+                let (x, y) = value.split_once(',').ok_or("--workspace expects X,Y")?;
+                hide = HideMethod::Workspace(WorkspacePosition {
+                    x: x.trim().parse()?,
+                    y: y.trim().parse()?,
+                });
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            _ if arg.starts_with('-') => {
+                eprintln!("Unknown option: {}\n\n{}", arg, USAGE);
+                std::process::exit(2);
+            }
+            _ => positional.push(arg),
+        }
+    }
+
+    let Some(app_id) = positional.first() else {
+        eprintln!("{}", USAGE);
+        std::process::exit(2);
+    };
+
+    let mut scratchpad = Scratchpad::new(app_id).hide_with(hide);
+    if let Some(command) = positional.get(1) {
+        scratchpad = scratchpad.command(command);
+    }
+    if let Some((width, height)) = size {
+        scratchpad = scratchpad.size(width, height);
+    }
+
+    let mut socket = WayfireSocket::connect().await?;
+    match scratchpad.toggle(&mut socket).await? {
+        ScratchpadState::Shown(id) => println!("Shown view {}", id),
+        ScratchpadState::Hidden(id) => println!("Hidden view {}", id),
+    }
+    Ok(())
+}
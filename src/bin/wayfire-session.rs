@@ -0,0 +1,91 @@
+use std::env;
+use std::error::Error;
+use std::path::PathBuf;
+use std::time::Duration;
+use wayfire_rs::ipc::WayfireSocket;
+use wayfire_rs::session::{restore_session, save_session, Session};
+
+const USAGE: &str = "Usage: wayfire-session save|restore [--timeout SECONDS] [FILE]
+
+Saves the placement of all open windows to FILE (default:
+$XDG_STATE_HOME/wayfire-rs/session.json), or puts windows back where they were
+saved, waiting up to --timeout seconds (default: 30) for them to open.";
+
+fn default_session_path() -> Option<PathBuf> {
+    let state_dir = env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state")))?;
+    Some(state_dir.join("wayfire-rs").join("session.json"))
+}
+
+#[pollster::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let mut args = env::args().skip(1);
+    let mut command = None;
+    let mut timeout = Duration::from_secs(30);
+    let mut session_path = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "save" | "restore" if command.is_none() => command = Some(arg),
+            "--timeout" => {
+                let seconds = args.next().and_then(|s| s.parse::<f64>().ok());
+                let Some(parsed) = seconds.and_then(|s| Duration::try_from_secs_f64(s).ok()) else {
+                    eprintln!("--timeout expects a number of seconds\n\n{}", USAGE);
+                    std::process::exit(2);
+                };
+                timeout = parsed;
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            _ if arg.starts_with('-') => {
+                eprintln!("Unknown option: {}\n\n{}", arg, USAGE);
+                std::process::exit(2);
+            }
+            _ => session_path = Some(PathBuf::from(arg)),
+        }
+    }
+
+    let Some(command) = command else {
+        eprintln!("{}", USAGE);
+        std::process::exit(2);
+    };
+    let session_path = session_path
+        .or_else(default_session_path)
+        .ok_or("Could not determine the session path, pass it explicitly")?;
+
+    let mut socket = WayfireSocket::connect().await?;
+
+    if command == "save" {
+        let session = save_session(&mut socket).await?;
+        session.save(&session_path)?;
+        println!(
+            "Saved {} windows to {}",
+            session.views.len(),
+            session_path.display()
+        );
+        return Ok(());
+    }
+
+    let session =
+        Session::load(&session_path).map_err(|e| format!("{}: {}", session_path.display(), e))?;
+    let summary = restore_session(&mut socket, &session, timeout).await?;
+
+    println!("Restored {} windows", summary.restored.len());
+    for failure in &summary.failed {
+        let saved = &session.views[failure.index];
+        eprintln!(
+            "  failed: {} ({}) as view {}",
+            saved.app_id, saved.title, failure.view_id
+        );
+        for error in &failure.errors {
+            eprintln!("    {}", error);
+        }
+    }
+    for saved in &summary.missing {
+        println!("  not found: {} ({})", saved.app_id, saved.title);
+    }
+    Ok(())
+}
@@ -0,0 +1,64 @@
+use std::env;
+use std::error::Error;
+use wayfire_rs::ipc::WayfireSocket;
+use wayfire_rs::swallow::{SwallowEvent, Swallower, DEFAULT_TERMINALS};
+
+const USAGE: &str = "Usage: wayfire-swallow [--terminal APP_ID]... [--exclude APP_ID]...
+
+Minimizes a terminal while a window started from it is open, putting the
+window in the terminal's place, and restores the terminal when the window
+closes.
+
+  --terminal APP_ID  Swallow terminals with this app-id (replaces the defaults)
+  --exclude APP_ID   Never let windows with this app-id swallow their terminal";
+
+#[pollster::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let mut args = env::args().skip(1);
+    let mut terminals = Vec::new();
+    let mut swallower = Swallower::new();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--terminal" => terminals.push(args.next().ok_or("--terminal expects an app-id")?),
+            "--exclude" => {
+                let app_id = args.next().ok_or("--exclude expects an app-id")?;
+                swallower = swallower.exclude(&app_id);
+            }
+            "-h" | "--help" => {
+                println!(
+                    "{}\n\nDefault terminals: {}",
+                    USAGE,
+                    DEFAULT_TERMINALS.join(", ")
+                );
+                return Ok(());
+            }
+            _ => {
+                eprintln!("Unknown argument: {}\n\n{}", arg, USAGE);
+                std::process::exit(2);
+            }
+        }
+    }
+
+    if !terminals.is_empty() {
+        swallower = swallower.terminals(terminals);
+    }
+
+    let mut socket = WayfireSocket::connect().await?;
+    swallower
+        .run(&mut socket, |event| match event {
+            SwallowEvent::Swallowed { view, terminal } => println!(
+                "View {} ({}) swallowed terminal {}",
+                view.id, view.app_id, terminal
+            ),
+            SwallowEvent::Restored(terminal) => println!("Restored terminal {}", terminal),
+            SwallowEvent::SwallowFailed { view, error } => {
+                eprintln!("Failed to swallow for view {}: {}", view.id, error)
+            }
+            SwallowEvent::RestoreFailed { view, error } => {
+                eprintln!("Failed to restore after view {}: {}", view.id, error)
+            }
+        })
+        .await?;
+    Ok(())
+}
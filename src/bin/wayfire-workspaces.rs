@@ -0,0 +1,201 @@
+use std::env;
+use std::error::Error;
+use std::path::PathBuf;
+use wayfire_rs::ini::default_config_path;
+use wayfire_rs::ipc::WayfireSocket;
+use wayfire_rs::models::WorkspacePosition;
+use wayfire_rs::workspaces::{WorkspaceEntry, WorkspaceId, WorkspaceRegistry};
+
+const USAGE: &str = "Usage: wayfire-workspaces [--file FILE] COMMAND [ARGS]
+
+Names workspaces and stores icons and metadata for them in FILE (default:
+$XDG_CONFIG_HOME/wayfire-rs/workspaces.toml). WORKSPACE is [OUTPUT:]X,Y and
+defaults to the current workspace of the focused output.
+
+  list [--json]              Print the registered workspaces
+  current [--json]           Print the current workspace of each output
+  set [WORKSPACE] [--name NAME] [--icon ICON] [--meta KEY=VALUE]...
+                             Set a workspace's name, icon or metadata; an empty
+                             value unsets it
+  unset [WORKSPACE]          Forget a workspace's name, icon and metadata
+  goto NAME                  Switch to the workspace named NAME
+  send NAME [--follow]       Send the focused view to the workspace named NAME";
+
+fn describe(entry: &WorkspaceEntry) -> String {
+    let mut line = format!(
+        "{}: {}",
+        entry.id,
+        entry.info.name.as_deref().unwrap_or("-")
+    );
+    if let Some(icon) = &entry.info.icon {
+        line.push_str(&format!(" icon={}", icon));
+    }
+    for (key, value) in &entry.info.metadata {
+        line.push_str(&format!(" {}={}", key, value));
+    }
+    line
+}
+
+fn print_entries(entries: &[WorkspaceEntry], json: bool) -> Result<(), Box<dyn Error>> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(entries)?);
+    } else {
+        for entry in entries {
+            println!("{}", describe(entry));
+        }
+    }
+    Ok(())
+}
+
+// Parses [OUTPUT:]X,Y, or picks the focused output's current workspace.
+async fn resolve_workspace(
+    socket: &mut WayfireSocket,
+    arg: Option<&String>,
+) -> Result<WorkspaceId, Box<dyn Error>> {
+    let focused = socket.get_focused_output().await?;
+    let Some(arg) = arg else {
+        return Ok(WorkspaceId::current(&focused));
+    };
+
+    let outputs = socket.list_outputs().await?;
+    let (output_name, position) = arg
+        .rsplit_once(':')
+        .unwrap_or((focused.name.as_str(), arg.as_str()));
+    let output = outputs
+        .iter()
+        .find(|o| o.name == output_name)
+        .ok_or_else(|| format!("Output not found: {}", output_name))?;
+
+    let (x, y) = position
+        .split_once(',')
+        .ok_or("WORKSPACE expects [OUTPUT:]X,Y")?;
+    let position = WorkspacePosition {
+        x: x.trim().parse()?,
+        y: y.trim().parse()?,
+    };
+    if !output.workspace.contains(position) {
+        return Err(format!(
+            "{} has no workspace {},{}",
+            output.name, position.x, position.y
+        )
+        .into());
+    }
+
+    Ok(WorkspaceId::new(output, position))
+}
+
+fn non_empty(value: String) -> Option<String> {
+    (!value.is_empty()).then_some(value)
+}
+
+#[pollster::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let mut args = env::args().skip(1);
+    let mut registry_path = None;
+    let mut command = None;
+    let mut positional = Vec::new();
+    let mut json = false;
+    let mut follow = false;
+    let mut name = None;
+    let mut icon = None;
+    let mut metadata = Vec::new();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--file" => {
+                registry_path = Some(PathBuf::from(args.next().ok_or("--file expects a path")?))
+            }
+            "--json" => json = true,
+            "--follow" => follow = true,
+            "--name" => name = Some(args.next().ok_or("--name expects a name")?),
+            "--icon" => icon = Some(args.next().ok_or("--icon expects an icon")?),
+            "--meta" => {
+                let value = args.next().unwrap_or_default();
+                let (key, value) = value.split_once('=').ok_or("--meta expects KEY=VALUE")?;
+                metadata.push((key.to_string(), value.to_string()));
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            _ if arg.starts_with('-') => {
+                eprintln!("Unknown option: {}\n\n{}", arg, USAGE);
+                std::process::exit(2);
+            }
+            _ if command.is_none() => command = Some(arg),
+            _ => positional.push(arg),
+        }
+    }
+
+    let registry_path = registry_path
+        .or_else(|| default_config_path("workspaces.toml"))
+        .ok_or("Could not determine the registry path, pass it with --file")?;
+    let mut registry = WorkspaceRegistry::load_or_default(&registry_path)
+        .map_err(|e| format!("{}: {}", registry_path.display(), e))?;
+
+    match command.as_deref() {
+        Some("list") => print_entries(&registry.workspaces, json)?,
+        Some("current") => {
+            let mut socket = WayfireSocket::connect().await?;
+            let outputs = socket.list_outputs().await?;
+            print_entries(&registry.current(&outputs), json)?;
+        }
+        Some("set") => {
+            if name.is_none() && icon.is_none() && metadata.is_empty() {
+                return Err("set expects --name, --icon or --meta".into());
+            }
+
+            let mut socket = WayfireSocket::connect().await?;
+            let id = resolve_workspace(&mut socket, positional.first()).await?;
+            let info = registry.entry(&id);
+            if let Some(name) = name {
+                info.name = non_empty(name);
+            }
+            if let Some(icon) = icon {
+                info.icon = non_empty(icon);
+            }
+            for (key, value) in metadata {
+                match non_empty(value) {
+                    Some(value) => info.metadata.insert(key, value),
+                    None => info.metadata.remove(&key),
+                };
+            }
+
+            let entry = WorkspaceEntry {
+                id: id.clone(),
+                info: info.clone(),
+            };
+            registry.save(&registry_path)?;
+            println!("{}", describe(&entry));
+        }
+        Some("unset") => {
+            let mut socket = WayfireSocket::connect().await?;
+            let id = resolve_workspace(&mut socket, positional.first()).await?;
+            if registry.remove(&id).is_some() {
+                registry.save(&registry_path)?;
+                println!("Removed {}", id);
+            }
+        }
+        Some("goto") => {
+            let name = positional.first().ok_or("goto expects a workspace name")?;
+            let mut socket = WayfireSocket::connect().await?;
+            let id = socket.switch_to_named_workspace(&registry, name).await?;
+            println!("Switched to {}", id);
+        }
+        Some("send") => {
+            let name = positional.first().ok_or("send expects a workspace name")?;
+            let mut socket = WayfireSocket::connect().await?;
+            let view = socket.get_focused_view().await?;
+            let id = socket
+                .send_view_to_named_workspace(view.id, &registry, name, follow)
+                .await?;
+            println!("Sent view {} to {}", view.id, id);
+        }
+        _ => {
+            eprintln!("{}", USAGE);
+            std::process::exit(2);
+        }
+    }
+
+    Ok(())
+}
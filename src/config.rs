@@ -0,0 +1,974 @@
+use crate::models::{OutputMode, ParseError};
+use crate::schema::{ConfigSchema, OptionType};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+// A single option value, typed after the option's type when it is known
+// (from the reply or the plugin metadata) and inferred from its text otherwise.
+// Values that don't parse as their type are kept as `String`. Booleans and
+// numbers are (de)serialized as JSON booleans and numbers, everything else in
+// Wayfire's text format.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(into = "RawOptionValue", from = "RawOptionValue")]
+pub enum WayfireOptionValue {
+    Bool(bool),
+    Int(i64),
+    Double(f64),
+    String(String),
+    Color(Color),
+    KeyBinding(KeyBinding),
+    ButtonBinding(ButtonBinding),
+    Activator(Activator),
+    Animation(AnimationDuration),
+    OutputMode(OutputMode),
+}
+
+impl WayfireOptionValue {
+    pub fn parse(option_type: &OptionType, value: &str) -> Self {
+        fn typed<T: FromStr>(
+            value: &str,
+            variant: fn(T) -> WayfireOptionValue,
+        ) -> WayfireOptionValue {
+            value
+                .parse()
+                .map_or_else(|_| WayfireOptionValue::String(value.to_string()), variant)
+        }
+
+        match option_type {
+            OptionType::Bool => match value.trim() {
+                "true" | "1" => WayfireOptionValue::Bool(true),
+                "false" | "0" => WayfireOptionValue::Bool(false),
+                _ => WayfireOptionValue::String(value.to_string()),
+            },
+            OptionType::Int => typed(value.trim(), WayfireOptionValue::Int),
+            OptionType::Double => typed(value.trim(), WayfireOptionValue::Double),
+            OptionType::Color => typed(value, WayfireOptionValue::Color),
+            OptionType::Key => typed(value, WayfireOptionValue::KeyBinding),
+            OptionType::Button => typed(value, WayfireOptionValue::ButtonBinding),
+            OptionType::Activator => typed(value, WayfireOptionValue::Activator),
+            OptionType::Animation => typed(value, WayfireOptionValue::Animation),
+            OptionType::OutputMode => typed(value, WayfireOptionValue::OutputMode),
+            _ => WayfireOptionValue::String(value.to_string()),
+        }
+    }
+
+    // Best guess for values whose option type isn't known. Integers are only
+    // taken as such if written the way the number would be, so strings like
+    // `007` stay strings. Anything with a decimal point or exponent that
+    // parses is a double, which covers the `%f` form Wayfire reports doubles
+    // in (`1.000000`).
+    pub fn infer(value: &str) -> Self {
+        let trimmed = value.trim();
+
+        if value == "true" || value == "false" {
+            return WayfireOptionValue::Bool(value == "true");
+        }
+        if let Ok(int) = value.parse::<i64>() {
+            if int.to_string() == value {
+                return WayfireOptionValue::Int(int);
+            }
+        }
+        if value.contains(['.', 'e', 'E']) {
+            if let Ok(double) = value.parse::<f64>() {
+                if double.is_finite() {
+                    return WayfireOptionValue::Double(double);
+                }
+            }
+        }
+
+        // Durations without a unit would take plain numbers as milliseconds.
+        let has_unit = trimmed
+            .split_whitespace()
+            .next()
+            .is_some_and(|duration| duration.ends_with('s'));
+
+        let option_type = if trimmed.starts_with('#') {
+            OptionType::Color
+        } else if trimmed.contains('|') {
+            OptionType::Activator
+        } else if trimmed.contains("BTN_") {
+            OptionType::Button
+        } else if trimmed.contains("KEY_") || trimmed.starts_with('<') {
+            OptionType::Key
+        } else if has_unit && trimmed.parse::<AnimationDuration>().is_ok() {
+            OptionType::Animation
+        } else if matches!(trimmed.parse(), Ok(OutputMode::Custom { .. })) {
+            OptionType::OutputMode
+        } else {
+            OptionType::String
+        };
+
+        WayfireOptionValue::parse(&option_type, value)
+    }
+
+    fn from_json(value: &Value, option_type: Option<&OptionType>) -> Self {
+        if let Value::Bool(b) = value {
+            return WayfireOptionValue::Bool(*b);
+        }
+
+        let text = json_text(value);
+        match option_type {
+            Some(option_type) => WayfireOptionValue::parse(option_type, &text),
+            None => WayfireOptionValue::infer(&text),
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            WayfireOptionValue::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            WayfireOptionValue::Int(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    pub fn as_double(&self) -> Option<f64> {
+        match self {
+            WayfireOptionValue::Double(d) => Some(*d),
+            WayfireOptionValue::Int(i) => Some(*i as f64),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            WayfireOptionValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_color(&self) -> Option<&Color> {
+        match self {
+            WayfireOptionValue::Color(color) => Some(color),
+            _ => None,
+        }
+    }
+
+    pub fn as_key_binding(&self) -> Option<&KeyBinding> {
+        match self {
+            WayfireOptionValue::KeyBinding(binding) => Some(binding),
+            _ => None,
+        }
+    }
+
+    pub fn as_button_binding(&self) -> Option<&ButtonBinding> {
+        match self {
+            WayfireOptionValue::ButtonBinding(binding) => Some(binding),
+            _ => None,
+        }
+    }
+
+    pub fn as_activator(&self) -> Option<&Activator> {
+        match self {
+            WayfireOptionValue::Activator(activator) => Some(activator),
+            _ => None,
+        }
+    }
+
+    pub fn as_animation(&self) -> Option<&AnimationDuration> {
+        match self {
+            WayfireOptionValue::Animation(animation) => Some(animation),
+            _ => None,
+        }
+    }
+
+    pub fn as_output_mode(&self) -> Option<&OutputMode> {
+        match self {
+            WayfireOptionValue::OutputMode(mode) => Some(mode),
+            _ => None,
+        }
+    }
+}
+
+// The value in the format Wayfire uses in its config file and over IPC.
+impl fmt::Display for WayfireOptionValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WayfireOptionValue::Bool(b) => write!(f, "{}", b),
+            WayfireOptionValue::Int(i) => write!(f, "{}", i),
+            WayfireOptionValue::Double(d) => write!(f, "{}", d),
+            WayfireOptionValue::String(s) => write!(f, "{}", s),
+            WayfireOptionValue::Color(color) => write!(f, "{}", color),
+            WayfireOptionValue::KeyBinding(binding) => write!(f, "{}", binding),
+            WayfireOptionValue::ButtonBinding(binding) => write!(f, "{}", binding),
+            WayfireOptionValue::Activator(activator) => write!(f, "{}", activator),
+            WayfireOptionValue::Animation(animation) => write!(f, "{}", animation),
+            WayfireOptionValue::OutputMode(mode) => write!(f, "{}", mode),
+        }
+    }
+}
+
+impl FromStr for WayfireOptionValue {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(WayfireOptionValue::infer(s))
+    }
+}
+
+impl From<String> for WayfireOptionValue {
+    fn from(value: String) -> Self {
+        WayfireOptionValue::infer(&value)
+    }
+}
+
+impl From<WayfireOptionValue> for String {
+    fn from(value: WayfireOptionValue) -> Self {
+        value.to_string()
+    }
+}
+
+// The JSON form of `WayfireOptionValue`.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(untagged)]
+enum RawOptionValue {
+    Bool(bool),
+    Int(i64),
+    Double(f64),
+    String(String),
+}
+
+impl From<RawOptionValue> for WayfireOptionValue {
+    fn from(value: RawOptionValue) -> Self {
+        match value {
+            RawOptionValue::Bool(b) => WayfireOptionValue::Bool(b),
+            RawOptionValue::Int(i) => WayfireOptionValue::Int(i),
+            RawOptionValue::Double(d) => WayfireOptionValue::Double(d),
+            // Booleans and numbers would have been serialized as such, so a
+            // string that looks like one is a string option.
+            RawOptionValue::String(s) => match WayfireOptionValue::infer(&s) {
+                WayfireOptionValue::Bool(_)
+                | WayfireOptionValue::Int(_)
+                | WayfireOptionValue::Double(_) => WayfireOptionValue::String(s),
+                value => value,
+            },
+        }
+    }
+}
+
+impl From<WayfireOptionValue> for RawOptionValue {
+    fn from(value: WayfireOptionValue) -> Self {
+        match value {
+            WayfireOptionValue::Bool(b) => RawOptionValue::Bool(b),
+            WayfireOptionValue::Int(i) => RawOptionValue::Int(i),
+            WayfireOptionValue::Double(d) => RawOptionValue::Double(d),
+            other => RawOptionValue::String(other.to_string()),
+        }
+    }
+}
+
+// A JSON option value in the text form Wayfire reads, without the quotes
+// around strings.
+fn json_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+// RGBA color with components in 0.0..=1.0. Wayfire accepts `#RRGGBBAA`,
+// `#RRGGBB`, `#RGBA`, `#RGB` or four floats separated by spaces.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(try_from = "String", into = "String")]
+pub struct Color {
+    pub r: f64,
+    pub g: f64,
+    pub b: f64,
+    pub a: f64,
+}
+
+impl FromStr for Color {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let invalid = || ParseError(format!("invalid color: {}", s));
+
+        let components: Vec<f64> = match s.strip_prefix('#') {
+            Some(hex) => {
+                if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                    return Err(invalid());
+                }
+                let digits: Vec<u32> = hex.chars().filter_map(|c| c.to_digit(16)).collect();
+                let channels: Vec<u32> = match digits.len() {
+                    3 | 4 => digits.iter().map(|d| d * 17).collect(),
+                    6 | 8 => digits.chunks(2).map(|c| c[0] * 16 + c[1]).collect(),
+                    _ => return Err(invalid()),
+                };
+                channels.into_iter().map(|c| c as f64 / 255.0).collect()
+            }
+            None => s
+                .split_whitespace()
+                .map(|c| c.parse().map_err(|_| invalid()))
+                .collect::<Result<_, _>>()?,
+        };
+
+        match components[..] {
+            [r, g, b] => Ok(Color { r, g, b, a: 1.0 }),
+            [r, g, b, a] => Ok(Color { r, g, b, a }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let channel = |c: f64| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+        write!(
+            f,
+            "#{:02X}{:02X}{:02X}{:02X}",
+            channel(self.r),
+            channel(self.g),
+            channel(self.b),
+            channel(self.a)
+        )
+    }
+}
+
+impl TryFrom<String> for Color {
+    type Error = ParseError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Color> for String {
+    fn from(value: Color) -> Self {
+        value.to_string()
+    }
+}
+
+// Splits a binding like `<super> <shift> KEY_E` into its modifiers and the
+// remaining key or button name. `none` and empty strings are unbound.
+fn parse_binding(s: &str) -> Result<(Vec<String>, Option<String>), ParseError> {
+    let s = s.trim();
+    if s.is_empty() || s == "none" || s == "disabled" {
+        return Ok((Vec::new(), None));
+    }
+
+    let mut modifiers = Vec::new();
+    let mut rest = s;
+    while let Some(stripped) = rest.strip_prefix('<') {
+        let (modifier, remaining) = stripped
+            .split_once('>')
+            .ok_or_else(|| ParseError(format!("unclosed modifier in binding: {}", s)))?;
+        modifiers.push(modifier.trim().to_string());
+        rest = remaining.trim_start();
+    }
+
+    match rest.split_whitespace().collect::<Vec<_>>()[..] {
+        [] => Ok((modifiers, None)),
+        [name] => Ok((modifiers, Some(name.to_string()))),
+        _ => Err(ParseError(format!("invalid binding: {}", s))),
+    }
+}
+
+fn fmt_binding(
+    f: &mut fmt::Formatter<'_>,
+    modifiers: &[String],
+    name: Option<&str>,
+) -> fmt::Result {
+    let parts: Vec<String> = modifiers
+        .iter()
+        .map(|modifier| format!("<{}>", modifier))
+        .chain(name.map(str::to_string))
+        .collect();
+
+    if parts.is_empty() {
+        write!(f, "none")
+    } else {
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[serde(try_from = "String", into = "String")]
+pub struct KeyBinding {
+    pub modifiers: Vec<String>,
+    // `None` for modifier-only bindings like `<super>`.
+    pub key: Option<String>,
+}
+
+impl KeyBinding {
+    pub fn is_unbound(&self) -> bool {
+        self.modifiers.is_empty() && self.key.is_none()
+    }
+}
+
+impl FromStr for KeyBinding {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (modifiers, key) = parse_binding(s)?;
+        if key.as_ref().is_some_and(|key| !key.starts_with("KEY_")) {
+            return Err(ParseError(format!("invalid key binding: {}", s)));
+        }
+        Ok(KeyBinding { modifiers, key })
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_binding(f, &self.modifiers, self.key.as_deref())
+    }
+}
+
+impl TryFrom<String> for KeyBinding {
+    type Error = ParseError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<KeyBinding> for String {
+    fn from(value: KeyBinding) -> Self {
+        value.to_string()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[serde(try_from = "String", into = "String")]
+pub struct ButtonBinding {
+    pub modifiers: Vec<String>,
+    pub button: Option<String>,
+}
+
+impl ButtonBinding {
+    pub fn is_unbound(&self) -> bool {
+        self.button.is_none()
+    }
+}
+
+impl FromStr for ButtonBinding {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (modifiers, button) = parse_binding(s)?;
+        let valid = match &button {
+            Some(button) => button.starts_with("BTN_"),
+            None => modifiers.is_empty(),
+        };
+        if !valid {
+            return Err(ParseError(format!("invalid button binding: {}", s)));
+        }
+        Ok(ButtonBinding { modifiers, button })
+    }
+}
+
+impl fmt::Display for ButtonBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_binding(f, &self.modifiers, self.button.as_deref())
+    }
+}
+
+impl TryFrom<String> for ButtonBinding {
+    type Error = ParseError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<ButtonBinding> for String {
+    fn from(value: ButtonBinding) -> Self {
+        value.to_string()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ActivatorBinding {
+    Key(KeyBinding),
+    Button(ButtonBinding),
+    // Hotspots and touchpad gestures, e.g. `hotspot top-left 10 500`.
+    Other(String),
+}
+
+impl fmt::Display for ActivatorBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ActivatorBinding::Key(binding) => write!(f, "{}", binding),
+            ActivatorBinding::Button(binding) => write!(f, "{}", binding),
+            ActivatorBinding::Other(binding) => write!(f, "{}", binding),
+        }
+    }
+}
+
+// Any number of bindings separated by `|`, e.g. `<super> KEY_E | hotspot top 10 500`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[serde(try_from = "String", into = "String")]
+pub struct Activator {
+    pub bindings: Vec<ActivatorBinding>,
+}
+
+impl FromStr for Activator {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bindings = s
+            .split('|')
+            .map(str::trim)
+            .filter(|binding| !binding.is_empty() && *binding != "none")
+            .map(|binding| {
+                if binding.contains("BTN_") {
+                    binding.parse().map(ActivatorBinding::Button)
+                } else if binding.contains("KEY_") || binding.starts_with('<') {
+                    binding.parse().map(ActivatorBinding::Key)
+                } else {
+                    Ok(ActivatorBinding::Other(binding.to_string()))
+                }
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Activator { bindings })
+    }
+}
+
+impl fmt::Display for Activator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.bindings.is_empty() {
+            return write!(f, "none");
+        }
+
+        let bindings: Vec<String> = self.bindings.iter().map(|b| b.to_string()).collect();
+        write!(f, "{}", bindings.join(" | "))
+    }
+}
+
+impl TryFrom<String> for Activator {
+    type Error = ParseError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Activator> for String {
+    fn from(value: Activator) -> Self {
+        value.to_string()
+    }
+}
+
+// Animation durations like `300ms`, `1s` or `300ms circle`. Plain numbers are
+// milliseconds, as in older Wayfire configs.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[serde(try_from = "String", into = "String")]
+pub struct AnimationDuration {
+    pub milliseconds: u64,
+    pub easing: Option<String>,
+}
+
+impl FromStr for AnimationDuration {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ParseError(format!("invalid animation duration: {}", s));
+        let mut parts = s.split_whitespace();
+
+        let duration = parts.next().ok_or_else(invalid)?;
+        let milliseconds = if let Some(ms) = duration.strip_suffix("ms") {
+            ms.parse().map_err(|_| invalid())?
+        } else if let Some(seconds) = duration.strip_suffix('s') {
+            let seconds: f64 = seconds.parse().map_err(|_| invalid())?;
+            (seconds * 1000.0).round() as u64
+        } else {
+            duration.parse().map_err(|_| invalid())?
+        };
+
+        let easing = parts.next().map(str::to_string);
+        if parts.next().is_some() {
+            return Err(invalid());
+        }
+
+        Ok(AnimationDuration {
+            milliseconds,
+            easing,
+        })
+    }
+}
+
+impl fmt::Display for AnimationDuration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}ms", self.milliseconds)?;
+        if let Some(easing) = &self.easing {
+            write!(f, " {}", easing)?;
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<String> for AnimationDuration {
+    type Error = ParseError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<AnimationDuration> for String {
+    fn from(value: AnimationDuration) -> Self {
+        value.to_string()
+    }
+}
+
+// All options of the running compositor, by section and option name.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(transparent)]
+pub struct ConfigTree {
+    pub sections: BTreeMap<String, BTreeMap<String, WayfireOptionValue>>,
+    // Every value as Wayfire reported it, by full option name. Not kept
+    // when the tree is serialized.
+    #[serde(skip)]
+    raw: BTreeMap<String, String>,
+}
+
+impl ConfigTree {
+    // Builds the tree from a `wayfire/list-config-options` reply. Options are
+    // either plain values or objects carrying the value along with its type.
+    pub fn from_value(value: &Value) -> Result<Self, ParseError> {
+        let sections = value
+            .as_object()
+            .ok_or_else(|| ParseError("expected an object of sections".to_string()))?;
+
+        let mut tree = ConfigTree::default();
+        for (section, options) in sections {
+            let options = options
+                .as_object()
+                .ok_or_else(|| ParseError(format!("section {} is not an object", section)))?;
+
+            let typed = tree.sections.entry(section.clone()).or_default();
+            for (name, option) in options {
+                let (raw, option_type) = match option {
+                    Value::Object(fields) => {
+                        let option_type = fields
+                            .get("type")
+                            .and_then(Value::as_str)
+                            .map(|t| OptionType::from(t.to_string()));
+                        (fields.get("value").unwrap_or(&Value::Null), option_type)
+                    }
+                    other => (other, None),
+                };
+                let value = WayfireOptionValue::from_json(raw, option_type.as_ref());
+
+                typed.insert(name.clone(), value);
+                tree.raw
+                    .insert(format!("{}/{}", section, name), json_text(raw));
+            }
+        }
+
+        Ok(tree)
+    }
+
+    // Re-types every option known to the schema, replacing inferred types.
+    pub fn apply_schema(&mut self, schema: &ConfigSchema) {
+        for (section, options) in self.sections.iter_mut() {
+            for (name, value) in options.iter_mut() {
+                let full_name = format!("{}/{}", section, name);
+                if let Some(option) = schema.option(&full_name) {
+                    let text = match self.raw.get(&full_name) {
+                        Some(raw) => raw.clone(),
+                        None => value.to_string(),
+                    };
+                    *value = WayfireOptionValue::parse(&option.option_type, &text);
+                }
+            }
+        }
+    }
+
+    // The option's value exactly as Wayfire reported it, falling back to the
+    // typed value's text for trees that were deserialized or built by hand.
+    pub fn raw(&self, option: &str) -> Option<String> {
+        match self.raw.get(option) {
+            Some(raw) => Some(raw.clone()),
+            None => self.get(option).map(|value| value.to_string()),
+        }
+    }
+
+    pub fn section(&self, section: &str) -> Option<&BTreeMap<String, WayfireOptionValue>> {
+        self.sections.get(section)
+    }
+
+    // Looks up an option by its full name, e.g. `core/plugins`.
+    pub fn get(&self, option: &str) -> Option<&WayfireOptionValue> {
+        let (section, name) = option.split_once('/')?;
+        self.sections.get(section)?.get(name)
+    }
+
+    pub fn get_bool(&self, option: &str) -> Option<bool> {
+        self.get(option)?.as_bool()
+    }
+
+    pub fn get_int(&self, option: &str) -> Option<i64> {
+        self.get(option)?.as_int()
+    }
+
+    pub fn get_double(&self, option: &str) -> Option<f64> {
+        self.get(option)?.as_double()
+    }
+
+    pub fn get_str(&self, option: &str) -> Option<&str> {
+        self.get(option)?.as_str()
+    }
+
+    // Every option as `(section/option, value)`, sorted by name.
+    pub fn options(&self) -> impl Iterator<Item = (String, &WayfireOptionValue)> {
+        self.sections.iter().flat_map(|(section, options)| {
+            options
+                .iter()
+                .map(move |(name, value)| (format!("{}/{}", section, name), value))
+        })
+    }
+}
+
+// An option that differs between two config trees. `old` or `new` is `None`
+// when the option only exists on one side, e.g. because a plugin was loaded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigChange {
+    pub option: String,
+    pub old: Option<WayfireOptionValue>,
+    pub new: Option<WayfireOptionValue>,
+}
+
+impl fmt::Display for ConfigChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let show = |value: &Option<WayfireOptionValue>| match value {
+            Some(value) => value.to_string(),
+            None => "(unset)".to_string(),
+        };
+        write!(
+            f,
+            "{}: {} -> {}",
+            self.option,
+            show(&self.old),
+            show(&self.new)
+        )
+    }
+}
+
+// Lists the options whose value changed going from `a` to `b`, sorted by
+// option name.
+pub fn diff(a: &ConfigTree, b: &ConfigTree) -> Vec<ConfigChange> {
+    let mut changes: Vec<ConfigChange> = a
+        .options()
+        .filter(|(option, old)| b.get(option) != Some(*old))
+        .map(|(option, old)| ConfigChange {
+            new: b.get(&option).cloned(),
+            old: Some(old.clone()),
+            option,
+        })
+        .collect();
+
+    changes.extend(
+        b.options()
+            .filter(|(option, _)| a.get(option).is_none())
+            .map(|(option, new)| ConfigChange {
+                option,
+                old: None,
+                new: Some(new.clone()),
+            }),
+    );
+
+    changes.sort_by(|x, y| x.option.cmp(&y.option));
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn infer_keeps_integers_canonical() {
+        assert_eq!(WayfireOptionValue::infer("7"), WayfireOptionValue::Int(7));
+        assert_eq!(WayfireOptionValue::infer("-3"), WayfireOptionValue::Int(-3));
+        assert_eq!(
+            WayfireOptionValue::infer("007"),
+            WayfireOptionValue::String("007".to_string())
+        );
+        assert_eq!(
+            WayfireOptionValue::infer(" 7"),
+            WayfireOptionValue::String(" 7".to_string())
+        );
+    }
+
+    #[test]
+    fn infer_reads_fixed_point_doubles() {
+        assert_eq!(
+            WayfireOptionValue::infer("1.000000"),
+            WayfireOptionValue::Double(1.0)
+        );
+        assert_eq!(
+            WayfireOptionValue::infer("0.500000"),
+            WayfireOptionValue::Double(0.5)
+        );
+        assert_eq!(
+            WayfireOptionValue::infer("1.5"),
+            WayfireOptionValue::Double(1.5)
+        );
+        assert_eq!(
+            WayfireOptionValue::infer("1e3"),
+            WayfireOptionValue::Double(1000.0)
+        );
+        assert_eq!(
+            WayfireOptionValue::infer("1e400"),
+            WayfireOptionValue::String("1e400".to_string())
+        );
+    }
+
+    #[test]
+    fn infer_recognizes_structured_values() {
+        assert_eq!(
+            WayfireOptionValue::infer("true"),
+            WayfireOptionValue::Bool(true)
+        );
+        assert!(WayfireOptionValue::infer("#FF0000FF").as_color().is_some());
+        assert!(WayfireOptionValue::infer("<super> KEY_E")
+            .as_key_binding()
+            .is_some());
+        assert!(WayfireOptionValue::infer("BTN_LEFT")
+            .as_button_binding()
+            .is_some());
+        assert!(
+            WayfireOptionValue::infer("<super> KEY_E | hotspot top 10 500")
+                .as_activator()
+                .is_some()
+        );
+        assert_eq!(
+            WayfireOptionValue::infer("300ms circle"),
+            WayfireOptionValue::Animation(AnimationDuration {
+                milliseconds: 300,
+                easing: Some("circle".to_string()),
+            })
+        );
+        assert_eq!(
+            WayfireOptionValue::infer("hello world"),
+            WayfireOptionValue::String("hello world".to_string())
+        );
+    }
+
+    #[test]
+    fn parse_follows_the_option_type() {
+        assert_eq!(
+            WayfireOptionValue::parse(&OptionType::Bool, "1"),
+            WayfireOptionValue::Bool(true)
+        );
+        assert_eq!(
+            WayfireOptionValue::parse(&OptionType::Int, " 42 "),
+            WayfireOptionValue::Int(42)
+        );
+        assert_eq!(
+            WayfireOptionValue::parse(&OptionType::Double, "0.500000"),
+            WayfireOptionValue::Double(0.5)
+        );
+        assert_eq!(
+            WayfireOptionValue::parse(&OptionType::String, "007"),
+            WayfireOptionValue::String("007".to_string())
+        );
+        assert_eq!(
+            WayfireOptionValue::parse(&OptionType::Animation, "300"),
+            WayfireOptionValue::Animation(AnimationDuration {
+                milliseconds: 300,
+                easing: None,
+            })
+        );
+        // Values that don't parse as their type are kept as text.
+        assert_eq!(
+            WayfireOptionValue::parse(&OptionType::Int, "many"),
+            WayfireOptionValue::String("many".to_string())
+        );
+    }
+
+    #[test]
+    fn colors_accept_every_wayfire_form() {
+        let red = Color {
+            r: 1.0,
+            g: 0.0,
+            b: 0.0,
+            a: 1.0,
+        };
+        assert_eq!("#FF0000FF".parse::<Color>().unwrap(), red);
+        assert_eq!("#F00".parse::<Color>().unwrap(), red);
+        assert_eq!("1 0 0 1".parse::<Color>().unwrap(), red);
+        assert!("#F0".parse::<Color>().is_err());
+        assert_eq!(red.to_string(), "#FF0000FF");
+    }
+
+    #[test]
+    fn values_serialize_as_json_scalars() {
+        let json = |value: &WayfireOptionValue| serde_json::to_string(value).unwrap();
+        assert_eq!(json(&WayfireOptionValue::Bool(true)), "true");
+        assert_eq!(json(&WayfireOptionValue::Int(5)), "5");
+        assert_eq!(json(&WayfireOptionValue::Double(0.5)), "0.5");
+        assert_eq!(
+            json(&WayfireOptionValue::String("007".to_string())),
+            "\"007\""
+        );
+
+        let value: WayfireOptionValue = serde_json::from_str("\"42\"").unwrap();
+        assert_eq!(value, WayfireOptionValue::String("42".to_string()));
+        let value: WayfireOptionValue = serde_json::from_str("42").unwrap();
+        assert_eq!(value, WayfireOptionValue::Int(42));
+    }
+
+    #[test]
+    fn config_tree_types_live_values() {
+        let tree = ConfigTree::from_value(&serde_json::json!({
+            "core": {
+                "scale": "1.000000",
+                "plugins": { "type": "string", "value": "007" },
+                "xwayland": true,
+            }
+        }))
+        .unwrap();
+
+        assert_eq!(tree.get_double("core/scale"), Some(1.0));
+        assert_eq!(tree.get_str("core/plugins"), Some("007"));
+        assert_eq!(tree.get_bool("core/xwayland"), Some(true));
+        assert_eq!(tree.raw("core/scale").as_deref(), Some("1.000000"));
+    }
+
+    #[test]
+    fn diff_lists_changed_added_and_removed_options() {
+        let before = ConfigTree::from_value(&serde_json::json!({
+            "core": { "xwayland": true, "plugins": "alpha expo" },
+            "expo": { "toggle": "<super> KEY_E" },
+        }))
+        .unwrap();
+        let after = ConfigTree::from_value(&serde_json::json!({
+            "core": { "xwayland": true, "plugins": "alpha scale" },
+            "scale": { "duration": "300ms" },
+        }))
+        .unwrap();
+
+        let changes = diff(&before, &after);
+        let options: Vec<&str> = changes.iter().map(|c| c.option.as_str()).collect();
+        assert_eq!(options, ["core/plugins", "expo/toggle", "scale/duration"]);
+
+        assert_eq!(
+            changes[0].to_string(),
+            "core/plugins: alpha expo -> alpha scale"
+        );
+        assert_eq!(changes[1].new, None);
+        assert_eq!(changes[2].old, None);
+        assert!(changes[2].new.is_some());
+    }
+
+    #[test]
+    fn diff_of_equal_trees_is_empty() {
+        let tree = ConfigTree::from_value(&serde_json::json!({
+            "core": { "scale": "1.000000", "xwayland": "1" },
+        }))
+        .unwrap();
+
+        assert!(diff(&tree, &tree.clone()).is_empty());
+        assert!(diff(&ConfigTree::default(), &ConfigTree::default()).is_empty());
+    }
+}
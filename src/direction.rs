@@ -0,0 +1,91 @@
+use crate::models::{Direction, Geometry, Output, View};
+
+// Geometry in the global layout: view geometry is relative to its output.
+pub fn global_geometry(view: &View, output: &Output) -> Geometry {
+    view.geometry
+        .translated(output.geometry.x, output.geometry.y)
+}
+
+// Views that can be navigated to: mapped, focusable toplevels on the current
+// workspace of their output, with their global geometry.
+pub fn visible_views<'a>(views: &'a [View], outputs: &[Output]) -> Vec<(&'a View, Geometry)> {
+    views
+        .iter()
+        .filter(|view| view.mapped && view.focusable && view.is_toplevel() && !view.minimized)
+        .filter_map(|view| {
+            let output = outputs.iter().find(|o| o.id == view.output_id)?;
+            let current = output.workspace.position();
+            if !view.sticky && view.workspace(output) != current {
+                return None;
+            }
+            Some((view, global_geometry(view, output)))
+        })
+        .collect()
+}
+
+// Picks the candidate nearest to `from` in the given direction. Only
+// candidates whose center lies beyond `from`'s center count; the distance
+// across the direction weighs double so that aligned candidates win.
+pub fn nearest<T: Copy>(
+    from: &Geometry,
+    direction: Direction,
+    candidates: impl IntoIterator<Item = (T, Geometry)>,
+) -> Option<T> {
+    let (from_x, from_y) = from.center();
+    let (dx, dy) = direction.delta();
+
+    candidates
+        .into_iter()
+        .filter_map(|(item, geometry)| {
+            let (x, y) = geometry.center();
+            let along = (x - from_x) * dx + (y - from_y) * dy;
+            if along <= 0 {
+                return None;
+            }
+            let across = ((x - from_x) * dy).abs() + ((y - from_y) * dx).abs();
+            Some((along + 2 * across, item))
+        })
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, item)| item)
+}
+
+// The output next to `from` in the given direction.
+pub fn adjacent_output<'a>(
+    from: &Output,
+    direction: Direction,
+    outputs: &'a [Output],
+) -> Option<&'a Output> {
+    let id = nearest(
+        &from.geometry,
+        direction,
+        outputs
+            .iter()
+            .filter(|o| o.id != from.id)
+            .map(|o| (o.id, o.geometry)),
+    )?;
+    outputs.iter().find(|o| o.id == id)
+}
+
+// Maps output-local geometry onto another output, keeping the position
+// relative to the output's size. The size is kept, shrunk to fit if needed.
+pub fn relative_geometry(geometry: &Geometry, from: &Output, to: &Output) -> Geometry {
+    let scale = |value: i64, from: i64, to: i64| {
+        if from <= 0 {
+            value
+        } else {
+            (value as f64 * to as f64 / from as f64).round() as i64
+        }
+    };
+
+    let width = geometry.width.min(to.geometry.width);
+    let height = geometry.height.min(to.geometry.height);
+    let x = scale(geometry.x, from.geometry.width, to.geometry.width);
+    let y = scale(geometry.y, from.geometry.height, to.geometry.height);
+
+    Geometry {
+        x: x.clamp(0, (to.geometry.width - width).max(0)),
+        y: y.clamp(0, (to.geometry.height - height).max(0)),
+        width,
+        height,
+    }
+}
@@ -0,0 +1,258 @@
+use crate::ipc::WayfireSocket;
+use crate::models::{Event, View};
+use std::io;
+
+// Most-recently-used focus order. A tracker loaded from `last_focus_timestamp`
+// is enough for one-shot scripts (e.g. "focus the previous window"); cycling
+// through more than two windows and urgency need a long-lived tracker fed
+// with events via `handle_event`.
+#[derive(Debug, Clone, Default)]
+pub struct FocusTracker {
+    // View ids, most recently focused first.
+    order: Vec<i64>,
+    // Views demanding attention, most recent first.
+    urgent: Vec<i64>,
+    cycle: Option<Cycle>,
+}
+
+// The MRU order frozen at the start of a cycle, so focusing views while
+// cycling doesn't reorder what is being cycled through.
+#[derive(Debug, Clone)]
+struct Cycle {
+    views: Vec<i64>,
+    index: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum FocusScope {
+    #[default]
+    All,
+    // Views on the focused output's current workspace.
+    Workspace,
+    App(String),
+    // Views with the same app-id as the focused view.
+    SameApp,
+}
+
+pub const FOCUS_EVENTS: [&str; 4] = [
+    "view-focused",
+    "view-mapped",
+    "view-unmapped",
+    "view-hints-changed",
+];
+
+fn is_focus_candidate(view: &View) -> bool {
+    view.mapped && view.focusable && view.is_toplevel()
+}
+
+impl FocusTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_views(views: &[View]) -> Self {
+        let mut candidates: Vec<&View> = views.iter().filter(|v| is_focus_candidate(v)).collect();
+        candidates.sort_by_key(|view| std::cmp::Reverse(view.last_focus_timestamp));
+
+        FocusTracker {
+            order: candidates.iter().map(|view| view.id).collect(),
+            ..Self::default()
+        }
+    }
+
+    pub async fn load(socket: &mut WayfireSocket) -> io::Result<Self> {
+        Ok(Self::from_views(&socket.list_views().await?))
+    }
+
+    // Subscribes to the events the tracker needs.
+    pub async fn watch(socket: &mut WayfireSocket) -> io::Result<()> {
+        socket
+            .watch(Some(FOCUS_EVENTS.iter().map(|e| e.to_string()).collect()))
+            .await?;
+        Ok(())
+    }
+
+    pub fn mru(&self) -> &[i64] {
+        &self.order
+    }
+
+    pub fn urgent(&self) -> &[i64] {
+        &self.urgent
+    }
+
+    pub fn focused(&mut self, view_id: i64) {
+        self.order.retain(|id| *id != view_id);
+        self.order.insert(0, view_id);
+        self.urgent.retain(|id| *id != view_id);
+
+        // Focus moving anywhere but where the cycle put it ends the cycle.
+        let in_cycle = self
+            .cycle
+            .as_ref()
+            .is_some_and(|cycle| cycle.views.get(cycle.index) == Some(&view_id));
+        if !in_cycle {
+            self.cycle = None;
+        }
+    }
+
+    pub fn remove(&mut self, view_id: i64) {
+        self.order.retain(|id| *id != view_id);
+        self.urgent.retain(|id| *id != view_id);
+        if let Some(cycle) = &mut self.cycle {
+            cycle.views.retain(|id| *id != view_id);
+            cycle.index = cycle.index.min(cycle.views.len().saturating_sub(1));
+        }
+    }
+
+    pub fn handle_event(&mut self, event: &Event) {
+        match event {
+            Event::ViewFocused(view) => self.focused(view.id),
+            Event::ViewUnmapped(view) => self.remove(view.id),
+            Event::ViewMapped(view)
+                if is_focus_candidate(view) && !self.order.contains(&view.id) =>
+            {
+                self.order.push(view.id);
+            }
+            Event::Other(value) if value["event"] == "view-hints-changed" => {
+                let Some(view_id) = value["view"]["id"].as_i64() else {
+                    return;
+                };
+                self.urgent.retain(|id| *id != view_id);
+                if value["demands-attention"].as_bool() == Some(true) {
+                    self.urgent.insert(0, view_id);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Ends the current cycle, e.g. when the alt-tab modifier is released.
+    pub fn end_cycle(&mut self) {
+        self.cycle = None;
+    }
+
+    // Focuses the most recently focused view in the scope other than the
+    // focused one, i.e. toggles between the last two windows.
+    pub async fn focus_previous(
+        &mut self,
+        socket: &mut WayfireSocket,
+        scope: &FocusScope,
+    ) -> io::Result<Option<i64>> {
+        self.cycle = None;
+
+        let candidates = self.candidates(socket, scope).await?;
+        let focused = self.order.first().copied();
+        let Some(target) = candidates.into_iter().find(|id| Some(*id) != focused) else {
+            return Ok(None);
+        };
+
+        self.focus(socket, target).await.map(Some)
+    }
+
+    // Focuses the next view in MRU order. Repeated calls walk through every
+    // view in the scope until the cycle ends.
+    pub async fn focus_next_in_mru(
+        &mut self,
+        socket: &mut WayfireSocket,
+        scope: &FocusScope,
+    ) -> io::Result<Option<i64>> {
+        if self.cycle.is_none() {
+            let views = self.candidates(socket, scope).await?;
+            let index = match (self.order.first(), views.first()) {
+                (Some(focused), Some(first)) if focused == first => 0,
+                // The focused view isn't part of the scope; start before the
+                // first view so it gets focused first.
+                _ => views.len().saturating_sub(1),
+            };
+            self.cycle = Some(Cycle { views, index });
+        }
+
+        let Some(cycle) = &mut self.cycle else {
+            return Ok(None);
+        };
+        if cycle.views.is_empty() {
+            return Ok(None);
+        }
+
+        cycle.index = (cycle.index + 1) % cycle.views.len();
+        let target = cycle.views[cycle.index];
+        if self.order.first() == Some(&target) {
+            // The focused view is the only one in the scope.
+            return Ok(None);
+        }
+
+        self.focus(socket, target).await.map(Some)
+    }
+
+    // Focuses the view that most recently demanded attention.
+    pub async fn focus_urgent(&mut self, socket: &mut WayfireSocket) -> io::Result<Option<i64>> {
+        match self.urgent.first().copied() {
+            Some(view_id) => self.focus(socket, view_id).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    // Focuses the view that most recently demanded attention, or the previous
+    // view if none does.
+    pub async fn focus_urgent_or_last(
+        &mut self,
+        socket: &mut WayfireSocket,
+    ) -> io::Result<Option<i64>> {
+        match self.focus_urgent(socket).await? {
+            Some(view_id) => Ok(Some(view_id)),
+            None => self.focus_previous(socket, &FocusScope::All).await,
+        }
+    }
+
+    async fn focus(&mut self, socket: &mut WayfireSocket, view_id: i64) -> io::Result<i64> {
+        socket.set_focus(view_id).await?;
+        self.focused(view_id);
+        Ok(view_id)
+    }
+
+    // Views in the scope, in MRU order. Views the tracker hasn't seen yet
+    // follow by their focus timestamp.
+    async fn candidates(
+        &self,
+        socket: &mut WayfireSocket,
+        scope: &FocusScope,
+    ) -> io::Result<Vec<i64>> {
+        let views = socket.list_views().await?;
+        let mut candidates: Vec<&View> = views.iter().filter(|v| is_focus_candidate(v)).collect();
+
+        match scope {
+            FocusScope::All => {}
+            FocusScope::Workspace => {
+                let output = socket
+                    .get_focused_output()
+                    .await
+                    .map_err(|e| io::Error::other(e.to_string()))?;
+                let current = output.workspace.position();
+                candidates.retain(|view| {
+                    view.output_id == output.id
+                        && (view.sticky || view.workspace(&output) == current)
+                });
+            }
+            FocusScope::App(app_id) => candidates.retain(|view| &view.app_id == app_id),
+            FocusScope::SameApp => {
+                let focused = self
+                    .order
+                    .first()
+                    .and_then(|id| views.iter().find(|view| view.id == *id))
+                    .or_else(|| views.iter().find(|view| view.activated));
+                let app_id = focused.map(|view| view.app_id.clone()).unwrap_or_default();
+                candidates.retain(|view| view.app_id == app_id);
+            }
+        }
+
+        candidates.sort_by_key(|view| {
+            let position = self.order.iter().position(|id| *id == view.id);
+            (
+                position.unwrap_or(usize::MAX),
+                std::cmp::Reverse(view.last_focus_timestamp),
+            )
+        });
+
+        Ok(candidates.iter().map(|view| view.id).collect())
+    }
+}
@@ -0,0 +1,582 @@
+use crate::config::{Color, ConfigTree, WayfireOptionValue};
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::io::Write;
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+
+// Reader and writer for wayfire.ini that keeps the file as it was written:
+// comments, blank lines, ordering and spacing survive a load/save round trip,
+// and only the lines of options that are changed get rewritten.
+//
+// Following wf-config, `#` starts a comment unless escaped as `\#` (which is
+// how colors are written), and a trailing `\` continues a value on the next
+// line.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WayfireIni {
+    lines: Vec<Line>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Line {
+    // Blank lines, comments and anything else that isn't understood.
+    Verbatim(String),
+    Section {
+        name: String,
+        raw: String,
+    },
+    Option {
+        section: String,
+        key: String,
+        value: String,
+        raw: String,
+    },
+}
+
+// An option whose value in the file differs from the running compositor.
+// `live` is `None` for options the compositor doesn't know, e.g. of plugins
+// that aren't loaded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IniDifference {
+    pub option: String,
+    pub file: String,
+    pub live: Option<String>,
+}
+
+// $XDG_CONFIG_HOME, or ~/.config if it isn't set.
+pub fn config_home() -> Option<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+}
+
+// Where this crate's tools look for their own config files by default, e.g.
+// `$XDG_CONFIG_HOME/wayfire-rs/rules.toml`.
+pub fn default_config_path(file_name: &str) -> Option<PathBuf> {
+    Some(config_home()?.join("wayfire-rs").join(file_name))
+}
+
+impl WayfireIni {
+    // The config file Wayfire was started with, which it exports to its
+    // children, falling back to the default location.
+    pub fn default_path() -> Option<PathBuf> {
+        if let Some(path) = env::var_os("WAYFIRE_CONFIG_FILE") {
+            return Some(PathBuf::from(path));
+        }
+
+        Some(config_home()?.join("wayfire.ini"))
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Ok(Self::parse(&fs::read_to_string(path)?))
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        // Write to a temporary file first so a failed write can't truncate the
+        // user's config. A symlinked config (e.g. from a dotfiles repo) is
+        // written through, with the temporary file next to the real one.
+        let path = match fs::canonicalize(path.as_ref()) {
+            Ok(path) => path,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => path.as_ref().to_path_buf(),
+            Err(e) => return Err(e),
+        };
+        let mut temp = path.as_os_str().to_owned();
+        temp.push(".tmp");
+        let temp = PathBuf::from(temp);
+
+        // The config keeps its permissions, e.g. 0600 for one holding secrets.
+        let permissions = match fs::metadata(&path) {
+            Ok(metadata) => Some(metadata.permissions()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e),
+        };
+
+        let result = write_temp_file(&temp, &self.to_string(), permissions)
+            .and_then(|()| fs::rename(&temp, &path));
+        if result.is_err() {
+            let _ = fs::remove_file(&temp);
+        }
+        result
+    }
+
+    pub fn parse(contents: &str) -> Self {
+        let mut lines = Vec::new();
+        let mut section = String::new();
+        let mut raw_lines = contents.split_inclusive('\n');
+
+        while let Some(raw_line) = raw_lines.next() {
+            let mut raw = raw_line.to_string();
+            let trimmed = raw_line.trim();
+
+            if let Some(name) = trimmed
+                .strip_prefix('[')
+                .and_then(|rest| rest.strip_suffix(']'))
+            {
+                section = name.trim().to_string();
+                lines.push(Line::Section {
+                    name: section.clone(),
+                    raw,
+                });
+                continue;
+            }
+
+            let Some((key, _)) = trimmed
+                .split_once('=')
+                .filter(|_| !trimmed.starts_with('#'))
+            else {
+                lines.push(Line::Verbatim(raw));
+                continue;
+            };
+
+            while ends_with_continuation(&raw) {
+                match raw_lines.next() {
+                    Some(next) => raw.push_str(next),
+                    None => break,
+                }
+            }
+
+            lines.push(Line::Option {
+                section: section.clone(),
+                key: key.trim().to_string(),
+                value: parse_value(&raw),
+                raw,
+            });
+        }
+
+        WayfireIni { lines }
+    }
+
+    pub fn sections(&self) -> Vec<&str> {
+        let mut sections = Vec::new();
+        for line in &self.lines {
+            if let Line::Section { name, .. } = line {
+                if !sections.contains(&name.as_str()) {
+                    sections.push(name.as_str());
+                }
+            }
+        }
+        sections
+    }
+
+    // Every option as `(section/option, value)`, in file order.
+    pub fn options(&self) -> impl Iterator<Item = (String, &str)> {
+        self.lines.iter().filter_map(|line| match line {
+            Line::Option {
+                section,
+                key,
+                value,
+                ..
+            } => Some((format!("{}/{}", section, key), value.as_str())),
+            _ => None,
+        })
+    }
+
+    pub fn get(&self, section: &str, key: &str) -> Option<&str> {
+        // Like wf-config, the last occurrence of a duplicated option wins.
+        self.lines.iter().rev().find_map(|line| match line {
+            Line::Option {
+                section: s,
+                key: k,
+                value,
+                ..
+            } if s == section && k == key => Some(value.as_str()),
+            _ => None,
+        })
+    }
+
+    // Looks up an option by its full name, e.g. `core/plugins`.
+    pub fn get_option(&self, option: &str) -> Option<&str> {
+        let (section, key) = option.split_once('/')?;
+        self.get(section, key)
+    }
+
+    pub fn set(&mut self, section: &str, key: &str, value: &str) {
+        let existing = self.lines.iter().rposition(|line| {
+            matches!(line, Line::Option { section: s, key: k, .. } if s == section && k == key)
+        });
+
+        if let Some(index) = existing {
+            if let Line::Option {
+                value: old_value,
+                raw,
+                ..
+            } = &mut self.lines[index]
+            {
+                if old_value != value {
+                    *raw = rewrite_value(raw, value);
+                    *old_value = value.to_string();
+                }
+            }
+            return;
+        }
+
+        let line = Line::Option {
+            section: section.to_string(),
+            key: key.to_string(),
+            value: value.to_string(),
+            raw: format!("{} = {}\n", key, escape_value(value)),
+        };
+
+        // Add new options right after the last line belonging to the section,
+        // or in a new section at the end of the file.
+        let last_in_section = self.lines.iter().rposition(|line| match line {
+            Line::Option { section: s, .. } => s == section,
+            Line::Section { name, .. } => name == section,
+            Line::Verbatim(_) => false,
+        });
+
+        match last_in_section {
+            Some(index) => {
+                self.ensure_newline(index);
+                self.lines.insert(index + 1, line);
+            }
+            None => {
+                if let Some(last) = self.lines.len().checked_sub(1) {
+                    self.ensure_newline(last);
+                }
+                if !self.lines.is_empty() {
+                    self.lines.push(Line::Verbatim("\n".to_string()));
+                }
+                self.lines.push(Line::Section {
+                    name: section.to_string(),
+                    raw: format!("[{}]\n", section),
+                });
+                self.lines.push(line);
+            }
+        }
+    }
+
+    pub fn set_option(&mut self, option: &str, value: &str) -> bool {
+        match option.split_once('/') {
+            Some((section, key)) => {
+                self.set(section, key, value);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn remove(&mut self, section: &str, key: &str) -> bool {
+        let before = self.lines.len();
+        self.lines.retain(|line| {
+            !matches!(line, Line::Option { section: s, key: k, .. } if s == section && k == key)
+        });
+        self.lines.len() != before
+    }
+
+    // Compares the options set in the file with the running configuration.
+    // Options only present in `live` are left out, since that would list every
+    // option left at its default.
+    pub fn diff(&self, live: &ConfigTree) -> Vec<IniDifference> {
+        let mut differences: Vec<IniDifference> = Vec::new();
+
+        for (option, file_value) in self.options() {
+            if differences.iter().any(|d| d.option == option) {
+                continue;
+            }
+            let file_value = self.get_option(&option).unwrap_or(file_value);
+            let live_value = live.raw(&option);
+
+            let same = live_value
+                .as_ref()
+                .is_some_and(|live_value| same_value(live_value, file_value));
+            if !same {
+                differences.push(IniDifference {
+                    option,
+                    file: file_value.to_string(),
+                    live: live_value,
+                });
+            }
+        }
+
+        differences
+    }
+
+    // Writes the live value of the given options into the file, so runtime
+    // changes survive a restart. Values are written as Wayfire reports them,
+    // and options whose value didn't change keep the file's spelling.
+    pub fn persist<'a>(&mut self, live: &ConfigTree, options: impl IntoIterator<Item = &'a str>) {
+        for option in options {
+            let Some(value) = live.raw(option) else {
+                continue;
+            };
+            if self
+                .get_option(option)
+                .is_some_and(|file_value| same_value(file_value, &value))
+            {
+                continue;
+            }
+            self.set_option(option, &value);
+        }
+    }
+
+    // Lines are kept with their line endings, except possibly the last one.
+    fn ensure_newline(&mut self, index: usize) {
+        let (Line::Verbatim(raw) | Line::Section { raw, .. } | Line::Option { raw, .. }) =
+            &mut self.lines[index];
+        if !raw.ends_with('\n') {
+            raw.push('\n');
+        }
+    }
+}
+
+impl fmt::Display for WayfireIni {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in &self.lines {
+            match line {
+                Line::Verbatim(raw) | Line::Section { raw, .. } | Line::Option { raw, .. } => {
+                    write!(f, "{}", raw)?
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn write_temp_file(
+    path: &Path,
+    contents: &str,
+    permissions: Option<fs::Permissions>,
+) -> io::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    if let Some(permissions) = &permissions {
+        options.mode(permissions.mode());
+    }
+
+    let mut file = options.open(path)?;
+    file.write_all(contents.as_bytes())?;
+    // A leftover file from an earlier attempt keeps the mode it was created
+    // with.
+    if let Some(permissions) = permissions {
+        file.set_permissions(permissions)?;
+    }
+    Ok(())
+}
+
+// Wayfire reports values in its own canonical form, which needn't match how
+// they were written in the file (`true` vs `1`, hex vs float colors, ...).
+fn same_value(a: &str, b: &str) -> bool {
+    let as_bool = |value: &str| match value.trim() {
+        "true" | "1" => Some(true),
+        "false" | "0" => Some(false),
+        _ => None,
+    };
+
+    if let (Some(a), Some(b)) = (as_bool(a), as_bool(b)) {
+        return a == b;
+    }
+    if let (Ok(a), Ok(b)) = (a.trim().parse::<f64>(), b.trim().parse::<f64>()) {
+        return a == b;
+    }
+    // Compared at the 8-bit precision of the hex form, so `0.1 0.1 0.1 1`
+    // matches the `#1A1A1AFF` Wayfire reports for it.
+    if let (Ok(a), Ok(b)) = (a.parse::<Color>(), b.parse::<Color>()) {
+        return a.to_string() == b.to_string();
+    }
+    a.trim() == b.trim() || WayfireOptionValue::infer(a) == WayfireOptionValue::infer(b)
+}
+
+fn ends_with_continuation(raw: &str) -> bool {
+    let line = raw.trim_end_matches(['\n', '\r']);
+    line.ends_with('\\') && !line.ends_with("\\\\")
+}
+
+// Index of the first `#` that isn't escaped, where a comment starts.
+fn comment_start(text: &str) -> Option<usize> {
+    let mut escaped = false;
+    for (index, c) in text.char_indices() {
+        match c {
+            '\\' if !escaped => escaped = true,
+            '#' if !escaped => return Some(index),
+            _ => escaped = false,
+        }
+    }
+    None
+}
+
+fn parse_value(raw: &str) -> String {
+    let value = raw.split_once('=').map_or("", |(_, value)| value);
+
+    let joined: Vec<&str> = value
+        .lines()
+        .map(|line| {
+            let line = comment_start(line).map_or(line, |index| &line[..index]);
+            let line = line.trim_end();
+            line.strip_suffix('\\').unwrap_or(line).trim()
+        })
+        .collect();
+
+    joined.join(" ").trim().replace("\\#", "#")
+}
+
+fn escape_value(value: &str) -> String {
+    value.replace('#', "\\#")
+}
+
+// Replaces the value in an option's line, keeping the key, the spacing around
+// `=` and any trailing comment.
+fn rewrite_value(raw: &str, value: &str) -> String {
+    let Some(equals) = raw.find('=') else {
+        return raw.to_string();
+    };
+    let (key_part, rest) = raw.split_at(equals + 1);
+
+    // Values that used to span several lines are collapsed onto the first one,
+    // keeping whichever line carried the trailing comment.
+    let first_line = rest.lines().next().unwrap_or_default();
+    let leading = &first_line[..first_line.len() - first_line.trim_start().len()];
+    let comment = rest
+        .lines()
+        .find_map(|line| comment_start(line).map(|index| (line, index)))
+        .map(|(line, index)| {
+            let before = &line[..index];
+            let spacing = &before[before.trim_end().len()..];
+            format!("{}{}", spacing, &line[index..])
+        })
+        .unwrap_or_default();
+    let leading = if first_line.trim().is_empty() {
+        " "
+    } else {
+        leading
+    };
+
+    format!(
+        "{}{}{}{}\n",
+        key_part,
+        leading,
+        escape_value(value),
+        comment
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = "# My config\n\
+        [core]\n\
+        plugins = alpha \\\n    \
+        expo \\\n    \
+        scale # the essentials\n\
+        xwayland = 1\n\
+        \n\
+        [decoration]\n\
+        active_color = \\#1A1A1AFF  # dark\n\
+        title_height=24\n";
+
+    #[test]
+    fn round_trips_unchanged() {
+        let ini = WayfireIni::parse(CONFIG);
+        assert_eq!(ini.to_string(), CONFIG);
+
+        let without_trailing_newline = "[core]\nplugins = alpha";
+        assert_eq!(
+            WayfireIni::parse(without_trailing_newline).to_string(),
+            without_trailing_newline
+        );
+    }
+
+    #[test]
+    fn joins_continuation_lines_and_unescapes_hashes() {
+        let ini = WayfireIni::parse(CONFIG);
+        assert_eq!(ini.get("core", "plugins"), Some("alpha expo scale"));
+        assert_eq!(ini.get("decoration", "active_color"), Some("#1A1A1AFF"));
+        assert_eq!(ini.get("decoration", "title_height"), Some("24"));
+        assert_eq!(ini.sections(), vec!["core", "decoration"]);
+    }
+
+    #[test]
+    fn set_rewrites_only_the_changed_line() {
+        let mut ini = WayfireIni::parse(CONFIG);
+        ini.set("decoration", "active_color", "#FF0000FF");
+        ini.set("decoration", "title_height", "24");
+        ini.set("core", "plugins", "alpha cube");
+
+        let expected = "# My config\n\
+            [core]\n\
+            plugins = alpha cube # the essentials\n\
+            xwayland = 1\n\
+            \n\
+            [decoration]\n\
+            active_color = \\#FF0000FF  # dark\n\
+            title_height=24\n";
+        assert_eq!(ini.to_string(), expected);
+        assert_eq!(ini.get("decoration", "active_color"), Some("#FF0000FF"));
+    }
+
+    #[test]
+    fn set_adds_missing_options_and_sections() {
+        let mut ini = WayfireIni::parse("[core]\nplugins = alpha");
+        ini.set("core", "xwayland", "false");
+        ini.set("expo", "toggle", "<super> KEY_E");
+
+        assert_eq!(
+            ini.to_string(),
+            "[core]\nplugins = alpha\nxwayland = false\n\n[expo]\ntoggle = <super> KEY_E\n"
+        );
+    }
+
+    #[test]
+    fn rewrite_value_keeps_spacing_and_comments() {
+        assert_eq!(rewrite_value("a=1\n", "2"), "a=2\n");
+        assert_eq!(rewrite_value("a =  1   # one\n", "2"), "a =  2   # one\n");
+        assert_eq!(rewrite_value("a = x \\\n  y\n", "z"), "a = z\n");
+        assert_eq!(rewrite_value("c = 1\n", "#FFF"), "c = \\#FFF\n");
+    }
+
+    #[test]
+    fn later_duplicates_win() {
+        let ini = WayfireIni::parse("[core]\na = 1\n[core]\na = 2\n");
+        assert_eq!(ini.get("core", "a"), Some("2"));
+    }
+
+    #[test]
+    fn equivalent_spellings_are_the_same_value() {
+        assert!(same_value("1", "true"));
+        assert!(same_value("1", "1.000000"));
+        assert!(same_value("0.1 0.1 0.1 1", "#1A1A1AFF"));
+        assert!(same_value("<super>  KEY_E", "<super> KEY_E"));
+        assert!(!same_value("1", "2"));
+        assert!(!same_value("#FF0000FF", "#00FF00FF"));
+    }
+
+    #[test]
+    fn persist_keeps_the_file_spelling_of_unchanged_values() {
+        let live = ConfigTree::from_value(&serde_json::json!({
+            "core": { "xwayland": "true", "plugins": "alpha expo" },
+            "decoration": { "title_height": "30" },
+        }))
+        .unwrap();
+
+        let mut ini = WayfireIni::parse("[core]\nxwayland = 1\n[decoration]\ntitle_height = 24\n");
+        ini.persist(
+            &live,
+            ["core/xwayland", "decoration/title_height", "core/plugins"],
+        );
+
+        assert_eq!(
+            ini.to_string(),
+            "[core]\nxwayland = 1\nplugins = alpha expo\n[decoration]\ntitle_height = 30\n"
+        );
+    }
+
+    #[test]
+    fn save_keeps_permissions() {
+        let dir = env::temp_dir().join(format!("wayfire-rs-ini-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("wayfire.ini");
+        fs::write(&path, "[core]\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).unwrap();
+
+        WayfireIni::parse(CONFIG).save(&path).unwrap();
+
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert_eq!(fs::read_to_string(&path).unwrap(), CONFIG);
+        assert!(!dir.join("wayfire.ini.tmp").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
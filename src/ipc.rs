@@ -1,15 +1,23 @@
+use crate::config::{self, ConfigChange, ConfigTree};
+use crate::direction;
+use crate::ini::{IniDifference, WayfireIni};
 use crate::models::{
-    InputDevice, Layout, MsgTemplate, OptionValueResponse, Output, View, ViewAlpha,
-    WayfireConfiguration, WorkspaceSet,
+    ActionResult, Capabilities, Direction, Event, GridSlot, InputDevice, KeyboardState, Layout,
+    MsgTemplate, OptionValueResponse, Output, OutputConfig, OutputId, OutputMode, OutputPosition,
+    OutputTransform, PluginNotLoaded, TiledEdges, View, ViewAlpha, WayfireConfiguration, Workspace,
+    WorkspacePosition, WorkspaceSet,
 };
+use crate::schema::ConfigSchema;
+use crate::workspaces::{WorkspaceId, WorkspaceRegistry};
 use serde_json::Value;
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::env;
 use std::error::Error;
 use std::io;
 use std::io::prelude::*;
 use std::os::unix::net::UnixStream;
 use std::path::Path;
+use std::time::{Duration, Instant};
 
 struct UnixStreamWrapper {
     stream: UnixStream,
@@ -17,7 +25,7 @@ struct UnixStreamWrapper {
 
 impl UnixStreamWrapper {
     async fn connect<P: AsRef<Path>>(path: P) -> io::Result<Self> {
-        UnixStream::connect(path).and_then(|stream| Ok(Self { stream }))
+        UnixStream::connect(path).map(|stream| Self { stream })
     }
     async fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
         self.stream.write_all(buf)
@@ -25,11 +33,27 @@ impl UnixStreamWrapper {
     async fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<()> {
         self.stream.read_exact(buf)
     }
+    async fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.stream.read(buf)
+    }
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.stream.set_read_timeout(timeout)
+    }
 }
 
 pub struct WayfireSocket {
     client: UnixStreamWrapper,
     pending_events: VecDeque<Value>,
+    // Last reported activation state per (plugin, output id), kept up to date
+    // from `plugin-activation-state-changed` events.
+    plugin_states: HashMap<(String, i64), bool>,
+    // Whether the last `watch` subscribed to those events.
+    watching_activation: bool,
+    api_version: Option<u32>,
+    capabilities: Option<Capabilities>,
+    // Workspace each output showed before its last switch, for going back
+    // and forth.
+    previous_workspaces: HashMap<OutputId, WorkspacePosition>,
 }
 
 impl WayfireSocket {
@@ -40,6 +64,11 @@ impl WayfireSocket {
         Ok(WayfireSocket {
             client,
             pending_events: VecDeque::new(),
+            plugin_states: HashMap::new(),
+            watching_activation: false,
+            api_version: None,
+            capabilities: None,
+            previous_workspaces: HashMap::new(),
         })
     }
 
@@ -69,6 +98,42 @@ impl WayfireSocket {
 
     pub async fn read_message(&mut self) -> io::Result<Value> {
         let len_buf = self.read_exact(4).await?;
+        self.read_message_body(len_buf).await
+    }
+
+    // Waits up to `timeout` for the next message and returns `None` if none
+    // arrived. Only the wait for its first byte is bounded, so a timeout never
+    // leaves part of a message unread.
+    pub async fn read_message_timeout(&mut self, timeout: Duration) -> io::Result<Option<Value>> {
+        if timeout.is_zero() {
+            return Ok(None);
+        }
+
+        let mut first = [0; 1];
+        self.client.set_read_timeout(Some(timeout))?;
+        let read = self.client.read(&mut first).await;
+        self.client.set_read_timeout(None)?;
+
+        match read {
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(_) => {}
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                return Ok(None)
+            }
+            Err(e) => return Err(e),
+        }
+
+        let mut len_buf = first.to_vec();
+        len_buf.extend(self.read_exact(3).await?);
+        self.read_message_body(len_buf).await.map(Some)
+    }
+
+    async fn read_message_body(&mut self, len_buf: Vec<u8>) -> io::Result<Value> {
         let len = u32::from_le_bytes(len_buf.try_into().unwrap()) as usize;
 
         let response_buf = self.read_exact(len).await?;
@@ -78,9 +143,63 @@ impl WayfireSocket {
             eprintln!("Error: {:?}", response);
         }
 
+        if response["event"] == "plugin-activation-state-changed" {
+            self.track_plugin_activation(&response);
+        }
+
+        if response["event"] == "wset-workspace-changed" {
+            self.track_workspace_change(&response);
+        }
+
         Ok(response)
     }
 
+    fn track_plugin_activation(&mut self, event: &Value) {
+        let (Some(plugin), Some(state)) = (event["plugin"].as_str(), event["state"].as_bool())
+        else {
+            return;
+        };
+        let output_id = event["output"].as_i64().unwrap_or(-1);
+
+        self.plugin_states
+            .insert((plugin.to_string(), output_id), state);
+    }
+
+    fn track_workspace_change(&mut self, event: &Value) {
+        let (Some(output_id), Ok(previous)) = (
+            event["output"].as_i64(),
+            serde_json::from_value::<WorkspacePosition>(event["previous-workspace"].clone()),
+        ) else {
+            return;
+        };
+
+        self.previous_workspaces.insert(output_id, previous);
+    }
+
+    // Activation state as last reported by the compositor. Only accurate while
+    // `plugin-activation-state-changed` events are being watched.
+    pub fn is_plugin_active(&self, plugin: &str, output_id: Option<OutputId>) -> bool {
+        self.plugin_states.iter().any(|((name, output), state)| {
+            *state && name == plugin && output_id.is_none_or(|id| id == *output)
+        })
+    }
+
+    pub async fn send_action(&mut self, msg: &MsgTemplate) -> io::Result<ActionResult> {
+        let response = self.send_json(msg).await?;
+
+        if let Some(error) = response.get("error") {
+            let error = error.as_str().map_or(error.to_string(), str::to_string);
+            return Err(io::Error::other(format!("{}: {}", msg.method, error)));
+        }
+
+        serde_json::from_value(response).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Failed to parse response: {}", e),
+            )
+        })
+    }
+
     pub async fn read_next_event(&mut self) -> io::Result<Value> {
         match self.pending_events.pop_front() {
             Some(event) => Ok(event),
@@ -88,6 +207,19 @@ impl WayfireSocket {
         }
     }
 
+    pub async fn read_event(&mut self) -> io::Result<Event> {
+        self.read_next_event().await.map(Event::from)
+    }
+
+    // Like `read_event`, but gives up after `timeout` and returns `None`.
+    pub async fn read_event_timeout(&mut self, timeout: Duration) -> io::Result<Option<Event>> {
+        if let Some(event) = self.pending_events.pop_front() {
+            return Ok(Some(Event::from(event)));
+        }
+
+        Ok(self.read_message_timeout(timeout).await?.map(Event::from))
+    }
+
     pub async fn list_views(&mut self) -> io::Result<Vec<View>> {
         let message = MsgTemplate {
             method: "window-rules/list-views".to_string(),
@@ -148,6 +280,158 @@ impl WayfireSocket {
         Ok(configuration)
     }
 
+    // Wayfire's API version (the date of the last API change, e.g. 20240705),
+    // fetched once and cached for the lifetime of the socket. It is
+    // informational only: nothing in this crate depends on it, and which
+    // plugins and methods are available is better checked with
+    // `capabilities`.
+    pub async fn api_version(&mut self) -> io::Result<u32> {
+        if let Some(version) = self.api_version {
+            return Ok(version);
+        }
+
+        let version = self.get_configuration().await?.api_version;
+        self.api_version = Some(version);
+        Ok(version)
+    }
+
+    pub async fn list_methods(&mut self) -> io::Result<Vec<String>> {
+        let message = MsgTemplate {
+            method: "list-methods".to_string(),
+            data: None,
+        };
+
+        let response = self.send_json(&message).await?;
+
+        let methods = response.get("methods").ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Missing 'methods' field in response",
+            )
+        })?;
+
+        Ok(serde_json::from_value(methods.clone())?)
+    }
+
+    // Loaded plugins and available IPC methods, read once and cached until
+    // `refresh_capabilities` is called.
+    pub async fn capabilities(&mut self) -> io::Result<&Capabilities> {
+        if self.capabilities.is_none() {
+            self.refresh_capabilities().await?;
+        }
+
+        Ok(self.capabilities.get_or_insert_with(Capabilities::default))
+    }
+
+    pub async fn refresh_capabilities(&mut self) -> io::Result<&Capabilities> {
+        let plugins = self
+            .get_option_value("core/plugins")
+            .await
+            .ok()
+            .map(|plugins| Capabilities::parse_plugin_list(&plugins.value));
+        let methods = self.list_methods().await.ok();
+
+        Ok(self.capabilities.insert(Capabilities { plugins, methods }))
+    }
+
+    pub async fn require_plugin(&mut self, plugin: &str) -> io::Result<()> {
+        if self.capabilities().await?.is_plugin_available(plugin) {
+            Ok(())
+        } else {
+            Err(PluginNotLoaded(plugin.to_string()).into())
+        }
+    }
+
+    pub async fn list_plugins(&mut self) -> io::Result<Vec<String>> {
+        let plugins = self.get_option_value("core/plugins").await?;
+        Ok(Capabilities::parse_plugin_list(&plugins.value))
+    }
+
+    pub async fn is_plugin_loaded(&mut self, plugin: &str) -> io::Result<bool> {
+        Ok(self.list_plugins().await?.iter().any(|p| p == plugin))
+    }
+
+    pub async fn enable_plugin(&mut self, plugin: &str) -> io::Result<()> {
+        self.update_plugin_list(plugin, true).await
+    }
+
+    pub async fn disable_plugin(&mut self, plugin: &str) -> io::Result<()> {
+        self.update_plugin_list(plugin, false).await
+    }
+
+    // Rewrites `core/plugins` in a single request, keeping the other entries
+    // as they were, then waits for Wayfire to (un)load the plugin.
+    async fn update_plugin_list(&mut self, plugin: &str, enabled: bool) -> io::Result<()> {
+        let current = self.get_option_value("core/plugins").await?.value;
+        let mut entries: Vec<&str> = current.split_whitespace().collect();
+
+        let loaded = entries
+            .iter()
+            .any(|entry| Capabilities::plugin_name(entry) == plugin);
+        if loaded == enabled {
+            return Ok(());
+        }
+
+        if enabled {
+            entries.push(plugin);
+        } else {
+            entries.retain(|entry| Capabilities::plugin_name(entry) != plugin);
+        }
+
+        self.send_action(&MsgTemplate {
+            method: "wayfire/set-config-options".to_string(),
+            data: Some(serde_json::json!({ "core/plugins": entries.join(" ") })),
+        })
+        .await?;
+
+        self.wait_for_plugin_reload(plugin, enabled).await
+    }
+
+    // Plugins are (un)loaded when Wayfire reloads its configuration, shortly
+    // after the option changes. Waits until `core/plugins` lists the plugin
+    // (or no longer does) and, for plugins with a probe method, until the
+    // method is registered (or gone). Events arriving meanwhile are kept for
+    // `read_event`.
+    async fn wait_for_plugin_reload(&mut self, plugin: &str, enabled: bool) -> io::Result<()> {
+        const POLL_INTERVAL: Duration = Duration::from_millis(50);
+        const TIMEOUT: Duration = Duration::from_secs(2);
+
+        let deadline = Instant::now() + TIMEOUT;
+        loop {
+            let capabilities = self.refresh_capabilities().await?;
+            let listed = capabilities
+                .plugins
+                .as_ref()
+                .is_none_or(|plugins| plugins.iter().any(|p| p == plugin) == enabled);
+            let registered = match (Capabilities::probe_method(plugin), &capabilities.methods) {
+                (Some(probe), Some(methods)) => methods.iter().any(|m| m == probe) == enabled,
+                _ => true,
+            };
+            if listed && registered {
+                return Ok(());
+            }
+
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    format!(
+                        "Wayfire did not {} plugin {}",
+                        if enabled { "load" } else { "unload" },
+                        plugin
+                    ),
+                ));
+            }
+
+            if let Some(event) = self
+                .read_message_timeout(POLL_INTERVAL.min(remaining))
+                .await?
+            {
+                self.pending_events.push_back(event);
+            }
+        }
+    }
+
     pub async fn get_option_value(&mut self, option: &str) -> io::Result<OptionValueResponse> {
         let message = MsgTemplate {
             method: "wayfire/get-config-option".to_string(),
@@ -162,14 +446,160 @@ impl WayfireSocket {
         Ok(option_value_response)
     }
 
-    pub async fn list_config_options(&mut self) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+    // Reads an option and parses its value, e.g. `get_option::<bool>("core/xwayland")`
+    // or `get_option::<Activator>("expo/toggle")`.
+    pub async fn get_option<T>(&mut self, option: &str) -> io::Result<T>
+    where
+        T: std::str::FromStr,
+        T::Err: std::fmt::Display,
+    {
+        let response = self.get_option_value(option).await?;
+        parse_option_value(&response.value)
+    }
+
+    // Sets several options in one request. Fails if Wayfire rejects any of
+    // them.
+    pub async fn set_option_values<I, K, V>(&mut self, options: I) -> io::Result<ActionResult>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<Value>,
+    {
+        let data: serde_json::Map<String, Value> = options
+            .into_iter()
+            .map(|(option, value)| (option.into(), value.into()))
+            .collect();
+
+        let message = MsgTemplate {
+            method: "wayfire/set-config-options".to_string(),
+            data: Some(Value::Object(data)),
+        };
+
+        let result = self.send_action(&message).await?;
+        if result.result != "ok" {
+            return Err(io::Error::other(format!(
+                "{}: {}",
+                message.method, result.result
+            )));
+        }
+
+        Ok(result)
+    }
+
+    // Like `set_option_values`, but checks every value against the plugin
+    // metadata first and sends nothing if one of them is invalid.
+    pub async fn set_option_values_validated<I, K>(
+        &mut self,
+        schema: &ConfigSchema,
+        options: I,
+    ) -> io::Result<ActionResult>
+    where
+        I: IntoIterator<Item = (K, String)>,
+        K: Into<String>,
+    {
+        let options: Vec<(String, String)> = options
+            .into_iter()
+            .map(|(option, value)| (option.into(), value))
+            .collect();
+
+        for (option, value) in &options {
+            schema
+                .validate(option, value)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        }
+
+        self.set_option_values(options).await
+    }
+
+    pub async fn load_config_schema(&mut self) -> io::Result<ConfigSchema> {
+        let configuration = self.get_configuration().await?;
+        ConfigSchema::load_dir(configuration.plugin_xml_dir)
+    }
+
+    pub async fn set_option_value(
+        &mut self,
+        option: &str,
+        value: impl Into<Value>,
+    ) -> io::Result<ActionResult> {
+        self.set_option_values([(option, value)]).await
+    }
+
+    pub async fn list_config_options(&mut self) -> io::Result<ConfigTree> {
         let message = MsgTemplate {
             method: "wayfire/list-config-options".to_string(),
             data: None,
         };
 
         let response = self.send_json(&message).await?;
-        Ok(response)
+        let config_tree = ConfigTree::from_value(&response)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        Ok(config_tree)
+    }
+
+    // Captures every live option, to be handed back to `restore_config` after
+    // experimenting with settings.
+    pub async fn snapshot_config(&mut self) -> io::Result<ConfigTree> {
+        self.list_config_options().await
+    }
+
+    // Sets every option that changed since the snapshot back to its old value,
+    // as Wayfire reported it, in a single request and returns what was
+    // reverted. Options that didn't exist when the snapshot was taken are left
+    // alone.
+    pub async fn restore_config(&mut self, snapshot: &ConfigTree) -> io::Result<Vec<ConfigChange>> {
+        let current = self.list_config_options().await?;
+
+        let changes: Vec<ConfigChange> = config::diff(&current, snapshot)
+            .into_iter()
+            .filter(|change| change.new.is_some())
+            .collect();
+
+        if !changes.is_empty() {
+            let options: Vec<(String, String)> = changes
+                .iter()
+                .filter_map(|change| Some((change.option.clone(), snapshot.raw(&change.option)?)))
+                .collect();
+
+            self.set_option_values(options).await?;
+        }
+
+        Ok(changes)
+    }
+
+    // Compares the config file (by default the one Wayfire was started with)
+    // with the running configuration.
+    pub async fn config_file_differences(
+        &mut self,
+        path: Option<&Path>,
+    ) -> io::Result<Vec<IniDifference>> {
+        let path = config_file_path(path)?;
+        let ini = WayfireIni::load(&path)?;
+        let live = self.list_config_options().await?;
+
+        Ok(ini.diff(&live))
+    }
+
+    // Writes the current value of the given options to the config file, so
+    // changes made at runtime survive a restart.
+    pub async fn persist_options(
+        &mut self,
+        path: Option<&Path>,
+        options: &[&str],
+    ) -> io::Result<()> {
+        let path = config_file_path(path)?;
+        let mut ini = WayfireIni::load(&path)?;
+        let live = self.list_config_options().await?;
+
+        if let Some(missing) = options.iter().find(|option| live.get(option).is_none()) {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Unknown option: {}", missing),
+            ));
+        }
+
+        ini.persist(&live, options.iter().copied());
+        ini.save(&path)
     }
 
     pub async fn get_output(&mut self, output_id: i64) -> io::Result<Output> {
@@ -264,6 +694,8 @@ impl WayfireSocket {
     }
 
     pub async fn get_view_alpha(&mut self, view_id: i64) -> io::Result<ViewAlpha> {
+        self.require_plugin("alpha").await?;
+
         let message = MsgTemplate {
             method: "wf/alpha/get-view-alpha".to_string(),
             data: Some(serde_json::json!({
@@ -285,6 +717,8 @@ impl WayfireSocket {
 
     #[allow(dead_code)]
     pub async fn set_view_alpha(&mut self, view_id: i64, alpha: f64) -> io::Result<Value> {
+        self.require_plugin("alpha").await?;
+
         let message = MsgTemplate {
             method: "wf/alpha/set-view-alpha".to_string(),
             data: Some(serde_json::json!({
@@ -298,6 +732,8 @@ impl WayfireSocket {
 
     #[allow(dead_code)]
     pub async fn get_tiling_layout(&mut self, wset: i64, x: i64, y: i64) -> io::Result<Layout> {
+        self.require_plugin("simple-tile").await?;
+
         let message = MsgTemplate {
             method: "simple-tile/get-layout".to_string(),
             data: Some(serde_json::json!({
@@ -331,6 +767,8 @@ impl WayfireSocket {
         y: i64,
         layout: &Layout,
     ) -> io::Result<Value> {
+        self.require_plugin("simple-tile").await?;
+
         let message = MsgTemplate {
             method: "simple-tile/set-layout".to_string(),
             data: Some(serde_json::json!({
@@ -348,6 +786,8 @@ impl WayfireSocket {
 
     #[allow(dead_code)]
     pub async fn set_view_always_on_top(&mut self, view_id: i64, state: bool) -> io::Result<Value> {
+        self.require_plugin("wm-actions").await?;
+
         let message = MsgTemplate {
             method: "wm-actions/set-always-on-top".to_string(),
             data: Some(serde_json::json!({
@@ -361,6 +801,8 @@ impl WayfireSocket {
 
     #[allow(dead_code)]
     pub async fn set_view_fullscreen(&mut self, view_id: i64, state: bool) -> io::Result<Value> {
+        self.require_plugin("wm-actions").await?;
+
         let message = MsgTemplate {
             method: "wm-actions/set-fullscreen".to_string(),
             data: Some(serde_json::json!({
@@ -372,62 +814,128 @@ impl WayfireSocket {
         self.send_json(&message).await
     }
 
-    pub async fn expo_toggle(&mut self) -> io::Result<Value> {
-        let message = MsgTemplate {
-            method: "expo/toggle".to_string(),
-            data: None,
-        };
+    pub async fn expo_toggle(&mut self, output_id: Option<OutputId>) -> io::Result<ActionResult> {
+        self.require_plugin("expo").await?;
 
-        self.send_json(&message).await
+        self.send_action(&activator_message("expo/toggle", output_id))
+            .await
     }
 
-    pub async fn scale_toggle(&mut self) -> io::Result<Value> {
+    // Switches the output to the given workspace and leaves expo, the same as
+    // picking a workspace from the overview. Wayfire can't be asked whether
+    // expo is open, so this needs `plugin-activation-state-changed` events to
+    // be watched on this socket and fails otherwise.
+    pub async fn expo_goto_workspace(
+        &mut self,
+        output_id: OutputId,
+        x: i64,
+        y: i64,
+    ) -> io::Result<ActionResult> {
+        if !self.watching_activation {
+            return Err(io::Error::other(
+                "expo state is unknown, watch plugin-activation-state-changed events first",
+            ));
+        }
+
+        let result = self.switch_workspace(output_id, x, y).await?;
+
+        if self.is_expo_active(Some(output_id)) {
+            return self.expo_toggle(Some(output_id)).await;
+        }
+
+        Ok(result)
+    }
+
+    // Like `is_scale_active`, only accurate while activation events are watched.
+    pub fn is_expo_active(&self, output_id: Option<OutputId>) -> bool {
+        self.is_plugin_active("expo", output_id)
+    }
+
+    pub async fn scale_toggle(&mut self) -> io::Result<ActionResult> {
+        self.require_plugin("scale").await?;
+
         let message = MsgTemplate {
             method: "scale/toggle".to_string(),
             data: None,
         };
 
-        self.send_json(&message).await
+        self.send_action(&message).await
     }
 
-    #[allow(dead_code)]
-    pub async fn scale_toggle_all(&mut self) -> io::Result<Value> {
+    pub async fn scale_toggle_all(&mut self) -> io::Result<ActionResult> {
+        self.require_plugin("scale").await?;
+
         let message = MsgTemplate {
-            method: "expo/toggle_all".to_string(),
+            method: "scale/toggle_all".to_string(),
             data: None,
         };
 
-        self.send_json(&message).await
+        self.send_action(&message).await
     }
 
-    pub async fn cube_activate(&mut self) -> io::Result<Value> {
-        let message = MsgTemplate {
-            method: "cube/activate".to_string(),
-            data: None,
-        };
+    // Opens scale showing only the views whose title or app-id contains
+    // `filter`. The filter is handed to the scale-title-filter plugin through
+    // its `filter` option, so it also applies to the next scale opened by
+    // hand until it is cleared with an empty filter.
+    pub async fn scale_toggle_filtered(
+        &mut self,
+        filter: &str,
+        all_workspaces: bool,
+    ) -> io::Result<ActionResult> {
+        self.require_plugin("scale").await?;
+        self.require_plugin("scale-title-filter").await?;
 
-        self.send_json(&message).await
+        self.set_option_value("scale-title-filter/filter", filter)
+            .await?;
+
+        if all_workspaces {
+            self.scale_toggle_all().await
+        } else {
+            self.scale_toggle().await
+        }
     }
 
-    pub async fn cube_rotate_left(&mut self) -> io::Result<Value> {
-        let message = MsgTemplate {
-            method: "cube/rotate_left".to_string(),
-            data: None,
-        };
+    // Whether scale is shown, as last reported in
+    // `plugin-activation-state-changed` events. Those are only received after
+    // `watch` subscribed to them on this socket, until then this is false.
+    pub fn is_scale_active(&self, output_id: Option<OutputId>) -> bool {
+        self.is_plugin_active("scale", output_id)
+    }
 
-        self.send_json(&message).await
+    pub async fn cube_activate(&mut self, output_id: Option<OutputId>) -> io::Result<ActionResult> {
+        self.require_plugin("cube").await?;
+
+        self.send_action(&activator_message("cube/activate", output_id))
+            .await
     }
 
-    pub async fn cube_rotate_right(&mut self) -> io::Result<Value> {
-        let message = MsgTemplate {
-            method: "cube/rotate_right".to_string(),
-            data: None,
-        };
+    pub async fn cube_rotate_left(
+        &mut self,
+        output_id: Option<OutputId>,
+    ) -> io::Result<ActionResult> {
+        self.require_plugin("cube").await?;
 
-        self.send_json(&message).await
+        self.send_action(&activator_message("cube/rotate_left", output_id))
+            .await
+    }
+
+    pub async fn cube_rotate_right(
+        &mut self,
+        output_id: Option<OutputId>,
+    ) -> io::Result<ActionResult> {
+        self.require_plugin("cube").await?;
+
+        self.send_action(&activator_message("cube/rotate_right", output_id))
+            .await
+    }
+
+    pub fn is_cube_active(&self, output_id: Option<OutputId>) -> bool {
+        self.is_plugin_active("cube", output_id)
     }
 
     pub async fn toggle_showdesktop(&mut self) -> io::Result<Value> {
+        self.require_plugin("wm-actions").await?;
+
         let message = MsgTemplate {
             method: "wm-actions/toggle_showdesktop".to_string(),
             data: None,
@@ -436,6 +944,8 @@ impl WayfireSocket {
     }
 
     pub async fn set_view_sticky(&mut self, view_id: i64, state: bool) -> io::Result<Value> {
+        self.require_plugin("wm-actions").await?;
+
         let message = MsgTemplate {
             method: "wm-actions/set-sticky".to_string(),
             data: Some(serde_json::json!({
@@ -446,7 +956,14 @@ impl WayfireSocket {
         self.send_json(&message).await
     }
 
-    pub async fn send_view_to_workspace(&mut self, view_id: i32, x: i32, y: i32) -> io::Result<Value> {
+    pub async fn send_view_to_workspace(
+        &mut self,
+        view_id: i64,
+        x: i64,
+        y: i64,
+    ) -> io::Result<Value> {
+        self.require_plugin("vswitch").await?;
+
         let message = MsgTemplate {
             method: "vswitch/send-view".to_string(),
             data: Some(serde_json::json!({
@@ -460,6 +977,8 @@ impl WayfireSocket {
     }
 
     pub async fn send_view_to_back(&mut self, view_id: i64, state: bool) -> io::Result<Value> {
+        self.require_plugin("wm-actions").await?;
+
         let message = MsgTemplate {
             method: "wm-actions/send-to-back".to_string(),
             data: Some(serde_json::json!({
@@ -471,6 +990,8 @@ impl WayfireSocket {
     }
 
     pub async fn set_view_minimized(&mut self, view_id: i64, state: bool) -> io::Result<Value> {
+        self.require_plugin("wm-actions").await?;
+
         let message = MsgTemplate {
             method: "wm-actions/set-minimized".to_string(),
             data: Some(serde_json::json!({
@@ -517,6 +1038,12 @@ impl WayfireSocket {
     }
 
     pub async fn watch(&mut self, events: Option<Vec<String>>) -> io::Result<serde_json::Value> {
+        self.watching_activation = events.as_ref().is_none_or(|events| {
+            events
+                .iter()
+                .any(|event| event == "plugin-activation-state-changed")
+        });
+
         let mut data = serde_json::json!({});
         if let Some(events) = events {
             data["events"] = serde_json::json!(events);
@@ -561,15 +1088,50 @@ impl WayfireSocket {
         self.send_json(&message).await
     }
 
-    pub async fn assign_slot(&mut self, view_id: i64, slot: &str) -> io::Result<serde_json::Value> {
+    pub async fn assign_slot(&mut self, view_id: i64, slot: GridSlot) -> io::Result<ActionResult> {
+        self.require_plugin("grid").await?;
+
         let message = MsgTemplate {
-            method: format!("grid/{}", slot),
+            method: slot.method().to_string(),
             data: Some(serde_json::json!({
                 "view_id": view_id
             })),
         };
 
-        self.send_json(&message).await
+        let result = self.send_action(&message).await?;
+        if result.result != "ok" {
+            return Err(io::Error::other(format!(
+                "Failed to assign {} slot: {}",
+                slot, result.result
+            )));
+        }
+
+        Ok(result)
+    }
+
+    // Tiling is done through the grid plugin, so only edge sets matching a
+    // grid slot are supported. An empty set restores the view.
+    pub async fn set_view_tiled_edges(
+        &mut self,
+        view_id: i64,
+        edges: TiledEdges,
+    ) -> io::Result<ActionResult> {
+        let slot = match edges.grid_slot() {
+            Some(slot) => slot,
+            None if edges.is_empty() => GridSlot::Restore,
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("No grid slot matches tiled edges {:?}", edges),
+                ))
+            }
+        };
+
+        self.assign_slot(view_id, slot).await
+    }
+
+    pub async fn get_view_slot(&mut self, view_id: i64) -> io::Result<Option<GridSlot>> {
+        Ok(self.get_view(view_id).await?.grid_slot())
     }
 
     pub async fn set_focus(&mut self, view_id: i64) -> io::Result<serde_json::Value> {
@@ -583,6 +1145,311 @@ impl WayfireSocket {
         self.send_json(&message).await
     }
 
+    // Focuses the nearest visible view in the given direction, on any output.
+    pub async fn focus_direction(&mut self, direction: Direction) -> io::Result<Option<i64>> {
+        let (_, neighbour, _) = self.neighbour_in_direction(direction).await?;
+        let Some(neighbour) = neighbour else {
+            return Ok(None);
+        };
+
+        self.set_focus(neighbour.id).await?;
+        Ok(Some(neighbour.id))
+    }
+
+    // Swaps the focused view with its neighbour in the given direction, or
+    // moves it to the next output if there is no neighbour. Returns whether
+    // the view was moved.
+    pub async fn move_direction(&mut self, direction: Direction) -> io::Result<bool> {
+        let (focused, neighbour, outputs) = self.neighbour_in_direction(direction).await?;
+        let Some(neighbour) = neighbour else {
+            return Ok(self.move_to_output(focused.id, direction).await?.is_some());
+        };
+
+        let output_of = |view: &View| outputs.iter().find(|o| o.id == view.output_id);
+        let (Some(focused_output), Some(neighbour_output)) =
+            (output_of(&focused), output_of(&neighbour))
+        else {
+            return Ok(false);
+        };
+
+        let to = neighbour.workspace_geometry(neighbour_output);
+        let from = focused.workspace_geometry(focused_output);
+        self.configure_view(
+            focused.id,
+            to.x,
+            to.y,
+            to.width,
+            to.height,
+            Some(neighbour_output.id),
+        )
+        .await?;
+        self.configure_view(
+            neighbour.id,
+            from.x,
+            from.y,
+            from.width,
+            from.height,
+            Some(focused_output.id),
+        )
+        .await?;
+
+        Ok(true)
+    }
+
+    // Moves a view to the output next to its own in the given direction,
+    // keeping its position relative to the output. Returns the new output.
+    pub async fn move_to_output(
+        &mut self,
+        view_id: i64,
+        direction: Direction,
+    ) -> io::Result<Option<OutputId>> {
+        let view = self.get_view(view_id).await?;
+        let outputs = self.list_outputs().await?;
+
+        let Some(from) = outputs.iter().find(|o| o.id == view.output_id) else {
+            return Ok(None);
+        };
+        let Some(to) = direction::adjacent_output(from, direction, &outputs) else {
+            return Ok(None);
+        };
+
+        let geometry = direction::relative_geometry(&view.workspace_geometry(from), from, to);
+        self.configure_view(
+            view.id,
+            geometry.x,
+            geometry.y,
+            geometry.width,
+            geometry.height,
+            Some(to.id),
+        )
+        .await?;
+
+        Ok(Some(to.id))
+    }
+
+    // The focused view, the nearest visible view in the given direction, and
+    // the outputs the lookup was based on.
+    async fn neighbour_in_direction(
+        &mut self,
+        direction: Direction,
+    ) -> io::Result<(View, Option<View>, Vec<Output>)> {
+        let focused = self
+            .get_focused_view()
+            .await
+            .map_err(|e| io::Error::other(e.to_string()))?;
+        let views = self.list_views().await?;
+        let outputs = self.list_outputs().await?;
+
+        let Some(output) = outputs.iter().find(|o| o.id == focused.output_id) else {
+            return Ok((focused, None, outputs));
+        };
+        let from = direction::global_geometry(&focused, output);
+
+        let visible = direction::visible_views(&views, &outputs);
+        let neighbour = direction::nearest(
+            &from,
+            direction,
+            visible
+                .iter()
+                .filter(|(view, _)| view.id != focused.id)
+                .map(|(view, geometry)| (view.id, *geometry)),
+        )
+        .and_then(|id| views.iter().find(|view| view.id == id).cloned());
+
+        Ok((focused, neighbour, outputs))
+    }
+
+    // Switches the output to the given workspace, remembering the one it
+    // leaves for `workspace_back_and_forth`.
+    pub async fn switch_workspace(
+        &mut self,
+        output_id: OutputId,
+        x: i64,
+        y: i64,
+    ) -> io::Result<ActionResult> {
+        self.require_plugin("vswitch").await?;
+
+        let current = self.get_output(output_id).await?.workspace.position();
+
+        let message = MsgTemplate {
+            method: "vswitch/set-workspace".to_string(),
+            data: Some(serde_json::json!({
+                "x": x,
+                "y": y,
+                "output-id": output_id
+            })),
+        };
+        let result = self.send_action(&message).await?;
+
+        if current != (WorkspacePosition { x, y }) {
+            self.previous_workspaces.insert(output_id, current);
+        }
+
+        Ok(result)
+    }
+
+    // Relative workspace navigation on the focused output. Each returns the
+    // workspace switched to, or `None` at the edge of the grid without `wrap`.
+    pub async fn workspace_left(&mut self, wrap: bool) -> io::Result<Option<WorkspacePosition>> {
+        self.switch_relative(|workspace| workspace.neighbour(Direction::Left, wrap))
+            .await
+    }
+
+    pub async fn workspace_right(&mut self, wrap: bool) -> io::Result<Option<WorkspacePosition>> {
+        self.switch_relative(|workspace| workspace.neighbour(Direction::Right, wrap))
+            .await
+    }
+
+    pub async fn workspace_up(&mut self, wrap: bool) -> io::Result<Option<WorkspacePosition>> {
+        self.switch_relative(|workspace| workspace.neighbour(Direction::Up, wrap))
+            .await
+    }
+
+    pub async fn workspace_down(&mut self, wrap: bool) -> io::Result<Option<WorkspacePosition>> {
+        self.switch_relative(|workspace| workspace.neighbour(Direction::Down, wrap))
+            .await
+    }
+
+    // The next workspace in index order, continuing on the next row.
+    pub async fn workspace_next(&mut self, wrap: bool) -> io::Result<Option<WorkspacePosition>> {
+        self.switch_relative(|workspace| workspace.step(1, wrap))
+            .await
+    }
+
+    pub async fn workspace_prev(&mut self, wrap: bool) -> io::Result<Option<WorkspacePosition>> {
+        self.switch_relative(|workspace| workspace.step(-1, wrap))
+            .await
+    }
+
+    // Switches the focused output back to the workspace it showed before the
+    // last switch. Switches made by other clients are only seen while the
+    // socket watches `wset-workspace-changed`.
+    pub async fn workspace_back_and_forth(&mut self) -> io::Result<Option<WorkspacePosition>> {
+        let output = self
+            .get_focused_output()
+            .await
+            .map_err(|e| io::Error::other(e.to_string()))?;
+        let Some(previous) = self.previous_workspaces.get(&output.id).copied() else {
+            return Ok(None);
+        };
+
+        self.switch_workspace(output.id, previous.x, previous.y)
+            .await?;
+        Ok(Some(previous))
+    }
+
+    // Sends the view to a workspace on its output and switches there with it.
+    pub async fn send_view_and_follow(
+        &mut self,
+        view_id: i64,
+        x: i64,
+        y: i64,
+    ) -> io::Result<Value> {
+        let view = self.get_view(view_id).await?;
+        let current = self.get_output(view.output_id).await?.workspace.position();
+
+        let result = self.set_workspace(x, y, view_id, view.output_id).await?;
+        if result["result"] == "ok" && current != (WorkspacePosition { x, y }) {
+            self.previous_workspaces.insert(view.output_id, current);
+        }
+
+        Ok(result)
+    }
+
+    // Switches to the workspace registered under `name`, preferring one on
+    // the focused output.
+    pub async fn switch_to_named_workspace(
+        &mut self,
+        registry: &WorkspaceRegistry,
+        name: &str,
+    ) -> io::Result<WorkspaceId> {
+        let (id, output) = self.resolve_named_workspace(registry, name).await?;
+        self.switch_workspace(output.id, id.x, id.y).await?;
+        Ok(id)
+    }
+
+    // Sends the view to the workspace registered under `name`, moving it to
+    // the workspace's output first if needed.
+    pub async fn send_view_to_named_workspace(
+        &mut self,
+        view_id: i64,
+        registry: &WorkspaceRegistry,
+        name: &str,
+        follow: bool,
+    ) -> io::Result<WorkspaceId> {
+        let (id, output) = self.resolve_named_workspace(registry, name).await?;
+        let view = self.get_view(view_id).await?;
+
+        if view.output_id != output.id {
+            let from = self.get_output(view.output_id).await?;
+            let geometry =
+                direction::relative_geometry(&view.workspace_geometry(&from), &from, &output);
+            self.configure_view(
+                view.id,
+                geometry.x,
+                geometry.y,
+                geometry.width,
+                geometry.height,
+                Some(output.id),
+            )
+            .await?;
+        }
+
+        if follow {
+            self.send_view_and_follow(view_id, id.x, id.y).await?;
+        } else {
+            self.send_view_to_workspace(view_id, id.x, id.y).await?;
+        }
+        Ok(id)
+    }
+
+    async fn resolve_named_workspace(
+        &mut self,
+        registry: &WorkspaceRegistry,
+        name: &str,
+    ) -> io::Result<(WorkspaceId, Output)> {
+        let focused = self
+            .get_focused_output()
+            .await
+            .map_err(|e| io::Error::other(e.to_string()))?;
+        let id = registry
+            .find(name, Some(&focused.name))
+            .cloned()
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("No workspace named {}", name),
+                )
+            })?;
+
+        let outputs = self.list_outputs().await?;
+        let output = id.find_output(&outputs).cloned().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Workspace {} ({}) is not on a connected output", name, id),
+            )
+        })?;
+
+        Ok((id, output))
+    }
+
+    async fn switch_relative(
+        &mut self,
+        target: impl FnOnce(&Workspace) -> Option<WorkspacePosition>,
+    ) -> io::Result<Option<WorkspacePosition>> {
+        let output = self
+            .get_focused_output()
+            .await
+            .map_err(|e| io::Error::other(e.to_string()))?;
+        let Some(position) = target(&output.workspace) else {
+            return Ok(None);
+        };
+
+        self.switch_workspace(output.id, position.x, position.y)
+            .await?;
+        Ok(Some(position))
+    }
+
     pub async fn set_workspace(
         &mut self,
         x: i64,
@@ -590,6 +1457,8 @@ impl WayfireSocket {
         view_id: i64,
         output_id: i64,
     ) -> io::Result<Value> {
+        self.require_plugin("vswitch").await?;
+
         let message = MsgTemplate {
             method: "vswitch/set-workspace".to_string(),
             data: Some(serde_json::json!({
@@ -652,6 +1521,12 @@ impl WayfireSocket {
         self.send_json(&message).await
     }
 
+    pub async fn get_keyboard_state(&mut self) -> io::Result<KeyboardState> {
+        let response = self.get_keyboard_layout().await?;
+        let keyboard_state: KeyboardState = serde_json::from_value(response)?;
+        Ok(keyboard_state)
+    }
+
     #[allow(dead_code)]
     pub async fn set_keyboard_layout(&mut self, index: u32) -> io::Result<serde_json::Value> {
         let message = MsgTemplate {
@@ -663,4 +1538,125 @@ impl WayfireSocket {
         self.send_json(&message).await
     }
 
+    pub async fn get_output_config(&mut self, output_name: &str) -> io::Result<OutputConfig> {
+        let option = |name: &str| format!("output:{}/{}", output_name, name);
+
+        let mode = self.get_option_value(&option("mode")).await?;
+        let position = self.get_option_value(&option("position")).await?;
+        let scale = self.get_option_value(&option("scale")).await?;
+        let transform = self.get_option_value(&option("transform")).await?;
+
+        Ok(OutputConfig {
+            name: output_name.to_string(),
+            mode: parse_option_value(&mode.value)?,
+            position: parse_option_value(&position.value)?,
+            scale: parse_option_value(&scale.value)?,
+            transform: parse_option_value(&transform.value)?,
+        })
+    }
+
+    pub async fn set_output_config(&mut self, config: &OutputConfig) -> io::Result<ActionResult> {
+        self.set_option_values(config.to_options()).await
+    }
+
+    pub async fn set_output_mode(
+        &mut self,
+        output_name: &str,
+        mode: OutputMode,
+    ) -> io::Result<ActionResult> {
+        self.set_option_value(&format!("output:{}/mode", output_name), mode.to_string())
+            .await
+    }
+
+    pub async fn set_output_position(
+        &mut self,
+        output_name: &str,
+        position: OutputPosition,
+    ) -> io::Result<ActionResult> {
+        self.set_option_value(
+            &format!("output:{}/position", output_name),
+            position.to_string(),
+        )
+        .await
+    }
+
+    pub async fn set_output_scale(
+        &mut self,
+        output_name: &str,
+        scale: f64,
+    ) -> io::Result<ActionResult> {
+        self.set_option_value(&format!("output:{}/scale", output_name), scale.to_string())
+            .await
+    }
+
+    pub async fn set_output_transform(
+        &mut self,
+        output_name: &str,
+        transform: OutputTransform,
+    ) -> io::Result<ActionResult> {
+        self.set_option_value(
+            &format!("output:{}/transform", output_name),
+            transform.to_string(),
+        )
+        .await
+    }
+
+    pub async fn set_output_wset(
+        &mut self,
+        output_id: i64,
+        wset_index: i64,
+    ) -> io::Result<ActionResult> {
+        self.require_plugin("wsets").await?;
+
+        let message = MsgTemplate {
+            method: "wsets/set-output-wset".to_string(),
+            data: Some(serde_json::json!({
+                "output-id": output_id,
+                "wset-index": wset_index
+            })),
+        };
+
+        self.send_action(&message).await
+    }
+
+    pub async fn enable_output(&mut self, output_name: &str) -> io::Result<ActionResult> {
+        self.set_output_mode(output_name, OutputMode::Auto).await
+    }
+
+    pub async fn disable_output(&mut self, output_name: &str) -> io::Result<ActionResult> {
+        self.set_output_mode(output_name, OutputMode::Off).await
+    }
+}
+
+// Message for methods bound through Wayfire's IPC activators, which run on the
+// focused output unless `output_id` is given.
+fn activator_message(method: &str, output_id: Option<OutputId>) -> MsgTemplate {
+    MsgTemplate {
+        method: method.to_string(),
+        data: output_id.map(|id| serde_json::json!({ "output_id": id })),
+    }
+}
+
+fn parse_option_value<T>(value: &str) -> io::Result<T>
+where
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    value.parse().map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Failed to parse option value '{}': {}", value, e),
+        )
+    })
+}
+
+fn config_file_path(path: Option<&Path>) -> io::Result<std::path::PathBuf> {
+    path.map(Path::to_path_buf)
+        .or_else(WayfireIni::default_path)
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                "Could not determine the Wayfire config file",
+            )
+        })
 }
@@ -0,0 +1,142 @@
+use crate::ipc::WayfireSocket;
+use crate::models::{Event, View};
+use std::collections::HashMap;
+use std::io;
+
+// Per-window keyboard layouts. Wayfire's layout is global, so the layout in
+// use when a view loses focus is remembered and switched back to when the
+// view is focused again. Views without a remembered layout get the default
+// layout, if one is set, and otherwise keep the current one.
+#[derive(Debug, Clone, Default)]
+pub struct LayoutMemory {
+    scope: LayoutScope,
+    default_layout: Option<u32>,
+    layouts: HashMap<LayoutKey, u32>,
+    focused: Option<LayoutKey>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LayoutScope {
+    // Every view has its own layout.
+    #[default]
+    View,
+    // Views with the same app-id share a layout.
+    App,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum LayoutKey {
+    View(i64),
+    App(String),
+}
+
+// What `LayoutMemory::run` did, for reporting.
+#[derive(Debug)]
+pub enum LayoutEvent {
+    Switched(u32),
+    Failed(io::Error),
+}
+
+pub const LAYOUT_EVENTS: [&str; 2] = ["view-focused", "view-unmapped"];
+
+impl LayoutMemory {
+    pub fn new(scope: LayoutScope) -> Self {
+        LayoutMemory {
+            scope,
+            ..Self::default()
+        }
+    }
+
+    pub fn default_layout(mut self, index: u32) -> Self {
+        self.default_layout = Some(index);
+        self
+    }
+
+    fn key(&self, view: &View) -> LayoutKey {
+        match self.scope {
+            LayoutScope::View => LayoutKey::View(view.id),
+            LayoutScope::App => LayoutKey::App(view.app_id.clone()),
+        }
+    }
+
+    // The layout remembered for the view, if any.
+    pub fn layout_for(&self, view: &View) -> Option<u32> {
+        self.layouts.get(&self.key(view)).copied()
+    }
+
+    // Remembers the current layout for the view losing focus and switches
+    // to the one of the view gaining it. Returns the layout switched to.
+    pub async fn focused(
+        &mut self,
+        socket: &mut WayfireSocket,
+        view: &View,
+    ) -> io::Result<Option<u32>> {
+        // Panels, launchers and the like use whatever layout is active.
+        if !view.is_toplevel() {
+            return Ok(None);
+        }
+
+        let current = socket.get_keyboard_state().await?.layout_index;
+        if let Some(previous) = self.focused.take() {
+            self.layouts.insert(previous, current);
+        }
+
+        let key = self.key(view);
+        let target = self.layouts.get(&key).copied().or(self.default_layout);
+        self.focused = Some(key);
+
+        match target {
+            Some(index) if index != current => {
+                socket.set_keyboard_layout(index).await?;
+                Ok(Some(index))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    // Forgets the layout of an unmapped view. Layouts remembered per app-id
+    // are kept for the app's next view.
+    pub fn unmapped(&mut self, view: &View) {
+        let key = LayoutKey::View(view.id);
+        self.layouts.remove(&key);
+        if self.focused == Some(key) {
+            self.focused = None;
+        }
+    }
+
+    pub async fn handle_event(
+        &mut self,
+        socket: &mut WayfireSocket,
+        event: &Event,
+    ) -> io::Result<Option<u32>> {
+        match event {
+            Event::ViewFocused(view) => self.focused(socket, view).await,
+            Event::ViewUnmapped(view) => {
+                self.unmapped(view);
+                Ok(None)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    // Keeps switching layouts as focus changes, reporting each switch to
+    // `on_event`. Failing switches don't stop the daemon.
+    pub async fn run(
+        &mut self,
+        socket: &mut WayfireSocket,
+        mut on_event: impl FnMut(LayoutEvent),
+    ) -> io::Result<()> {
+        socket
+            .watch(Some(LAYOUT_EVENTS.iter().map(|e| e.to_string()).collect()))
+            .await?;
+
+        loop {
+            let event = socket.read_event().await?;
+            match self.handle_event(socket, &event).await {
+                Ok(Some(index)) => on_event(LayoutEvent::Switched(index)),
+                Ok(None) => {}
+                Err(e) => on_event(LayoutEvent::Failed(e)),
+            }
+        }
+    }
+}
@@ -1,2 +1,15 @@
+pub mod config;
+pub mod direction;
+pub mod focus;
+pub mod ini;
 pub mod ipc;
+pub mod keyboard;
 pub mod models;
+pub mod profiles;
+pub mod rules;
+pub mod scratchpad;
+pub mod schema;
+pub mod session;
+pub mod swallow;
+pub mod tiling;
+pub mod workspaces;
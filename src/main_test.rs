@@ -20,14 +20,14 @@ async fn main() -> Result<(), Box<dyn Error>> {
         .list_views()
         .await?
         .into_iter()
-        .filter(|view| view.role == "toplevel")
+        .filter(|view| view.is_toplevel())
         .collect::<Vec<_>>();
     let outputs = socket.list_outputs().await?;
     let wsets = socket.list_wsets().await?;
     let input_devices = socket.list_input_devices().await?;
     let mut rng = rand::rng();
-    let target_x: i32 = rng.random_range(0..3);
-    let target_y: i32 = rng.random_range(0..3);
+    let target_x: i64 = rng.random_range(0..3);
+    let target_y: i64 = rng.random_range(0..3);
     let state = true;
 
     for view in &views {
@@ -80,11 +80,11 @@ async fn main() -> Result<(), Box<dyn Error>> {
     }
 
     // toggle expo twice
-    match socket.expo_toggle().await {
+    match socket.expo_toggle(None).await {
         Ok(view_alpha) => print_json("toggle expo", view_alpha).await?,
         Err(e) => eprintln!("Failed to toggle expo: {}", e),
     }
-    match socket.expo_toggle().await {
+    match socket.expo_toggle(None).await {
         Ok(view_alpha) => print_json("toggle expo", view_alpha).await?,
         Err(e) => eprintln!("Failed to toggle expo: {}", e),
     }
@@ -99,17 +99,17 @@ async fn main() -> Result<(), Box<dyn Error>> {
         Err(e) => eprintln!("Failed to toggle expo: {}", e),
     }
 
-    match socket.cube_activate().await {
+    match socket.cube_activate(None).await {
         Ok(_) => println!("Cube activated successfully."),
         Err(e) => eprintln!("Failed to activate cube: {}", e),
     }
 
-    match socket.cube_rotate_left().await {
+    match socket.cube_rotate_left(None).await {
         Ok(_) => println!("Cube rotated left successfully."),
         Err(e) => eprintln!("Failed to rotate cube left: {}", e),
     }
 
-    match socket.cube_rotate_right().await {
+    match socket.cube_rotate_right(None).await {
         Ok(_) => println!("Cube rotated right successfully."),
         Err(e) => eprintln!("Failed to rotate cube right: {}", e),
     }
@@ -147,7 +147,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
     }
 
     // Assign slot
-    match socket.assign_slot(focused_view_id, "top-left").await {
+    match socket.assign_slot(focused_view_id, models::GridSlot::TopLeft).await {
         Ok(response) => print_json("assign_slot", response).await?,
         Err(e) => eprintln!("Failed to assign slot: {}", e),
     }
@@ -168,7 +168,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
         Err(e) => eprintln!("Failed to send view to back: {}", e),
     }
 
-    match socket.send_view_to_workspace(view_id as i32, target_x, target_y).await {
+    match socket.send_view_to_workspace(view_id, target_x, target_y).await {
         Ok(_) => println!("Sent view to workspace successfully."),
         Err(e) => eprintln!("Failed to send view to workspace: {}", e),
     }
@@ -1,5 +1,8 @@
+use crate::config::WayfireOptionValue;
 use serde::{Deserialize, Serialize};
 
+pub type OutputId = i64;
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct MsgTemplate {
     pub method: String,
@@ -7,6 +10,11 @@ pub struct MsgTemplate {
     pub data: Option<serde_json::Value>,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ActionResult {
+    pub result: String,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct InputDevice {
     pub id: i64,
@@ -23,64 +31,149 @@ pub struct OptionValueResponse {
     pub value: String,
 }
 
+impl OptionValueResponse {
+    // Parses the current value, e.g. `response.parse::<Color>()`.
+    pub fn parse<T: std::str::FromStr>(&self) -> Result<T, T::Err> {
+        self.value.trim().parse()
+    }
+
+    pub fn parse_default<T: std::str::FromStr>(&self) -> Result<T, T::Err> {
+        self.default.trim().parse()
+    }
+
+    pub fn typed_value(&self) -> WayfireOptionValue {
+        WayfireOptionValue::infer(&self.value)
+    }
+
+    pub fn typed_default(&self) -> WayfireOptionValue {
+        WayfireOptionValue::infer(&self.default)
+    }
+
+    pub fn is_default(&self) -> bool {
+        self.value == self.default
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct KeyboardState {
     pub layout_index: u32,
     pub layout_name: String,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+// The models below only require the fields needed to identify an object. Any
+// other field missing from the compositor's reply falls back to a default, and
+// fields this crate doesn't know about yet end up in `extra`, so replies from
+// older or newer Wayfire versions still decode. The same goes for the nested
+// `Geometry`, `Size` and `Workspace`, whose fields all default to zero.
+
+fn no_id() -> i64 {
+    -1
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
 pub struct WayfireConfiguration {
-    #[serde(rename = "api-version")]
+    // Informational, see `WayfireSocket::api_version`.
+    #[serde(rename = "api-version", default)]
     pub api_version: u32,
-    #[serde(rename = "build-branch")]
+    #[serde(rename = "build-branch", default)]
     pub build_branch: String,
-    #[serde(rename = "build-commit")]
+    #[serde(rename = "build-commit", default)]
     pub build_commit: String,
-    #[serde(rename = "plugin-path")]
+    #[serde(rename = "plugin-path", default)]
     pub plugin_path: String,
-    #[serde(rename = "plugin-xml-dir")]
+    #[serde(rename = "plugin-xml-dir", default)]
     pub plugin_xml_dir: String,
-    #[serde(rename = "xwayland-support")]
+    #[serde(rename = "xwayland-support", default)]
     pub xwayland_support: u32,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct View {
+    #[serde(default)]
     pub activated: bool,
-    #[serde(rename = "app-id")]
+    #[serde(rename = "app-id", default)]
     pub app_id: String,
-    #[serde(rename = "base-geometry")]
+    #[serde(rename = "base-geometry", default)]
     pub base_geometry: Geometry,
+    #[serde(default)]
     pub bbox: Geometry,
+    #[serde(default)]
     pub focusable: bool,
+    #[serde(default)]
     pub fullscreen: bool,
+    #[serde(default)]
     pub geometry: Geometry,
     pub id: i64,
-    #[serde(rename = "last-focus-timestamp")]
+    #[serde(rename = "last-focus-timestamp", default)]
     pub last_focus_timestamp: i64,
-    pub layer: String,
+    #[serde(default)]
+    pub layer: ViewLayer,
+    #[serde(default)]
     pub mapped: bool,
-    #[serde(rename = "max-size")]
+    #[serde(rename = "max-size", default)]
     pub max_size: Size,
-    #[serde(rename = "min-size")]
+    #[serde(rename = "min-size", default)]
     pub min_size: Size,
+    #[serde(default)]
     pub minimized: bool,
-    #[serde(rename = "output-id")]
+    #[serde(rename = "output-id", default = "no_id")]
     pub output_id: i64,
-    #[serde(rename = "output-name")]
+    #[serde(rename = "output-name", default)]
     pub output_name: String,
+    #[serde(default = "no_id")]
     pub parent: i64,
+    #[serde(default = "no_id")]
     pub pid: i64,
-    pub role: String,
+    #[serde(default)]
+    pub role: ViewRole,
+    #[serde(default)]
     pub sticky: bool,
-    #[serde(rename = "tiled-edges")]
-    pub tiled_edges: i64,
+    #[serde(rename = "tiled-edges", default)]
+    pub tiled_edges: TiledEdges,
+    #[serde(default)]
     pub title: String,
-    #[serde(rename = "type")]
-    pub type_field: String,
-    #[serde(rename = "wset-index")]
+    #[serde(rename = "type", default)]
+    pub type_field: ViewType,
+    #[serde(rename = "wset-index", default = "no_id")]
     pub wset_index: i64,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl View {
+    pub fn grid_slot(&self) -> Option<GridSlot> {
+        GridSlot::from_tiled_edges(self.tiled_edges)
+    }
+
+    pub fn is_toplevel(&self) -> bool {
+        self.role == ViewRole::Toplevel
+    }
+
+    // The workspace the view is on. View geometry is relative to the output's
+    // current workspace, so views on other workspaces lie outside of it.
+    pub fn workspace(&self, output: &Output) -> WorkspacePosition {
+        let width = output.geometry.width.max(1);
+        let height = output.geometry.height.max(1);
+        let center_x = self.geometry.x + self.geometry.width / 2;
+        let center_y = self.geometry.y + self.geometry.height / 2;
+
+        WorkspacePosition {
+            x: output.workspace.x + center_x.div_euclid(width),
+            y: output.workspace.y + center_y.div_euclid(height),
+        }
+    }
+
+    // The view's geometry relative to the workspace it is on.
+    pub fn workspace_geometry(&self, output: &Output) -> Geometry {
+        let workspace = self.workspace(output);
+        Geometry {
+            x: self.geometry.x - (workspace.x - output.workspace.x) * output.geometry.width,
+            y: self.geometry.y - (workspace.y - output.workspace.y) * output.geometry.height,
+            ..self.geometry
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -96,7 +189,8 @@ pub struct Layout {
     pub vertical_split: Vec<Layout>,
 }
 
-#[derive(Eq, PartialEq, Copy, Clone, Serialize, Deserialize, Debug)]
+#[derive(Eq, PartialEq, Copy, Clone, Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
 pub struct Geometry {
     pub height: i64,
     pub width: i64,
@@ -104,6 +198,20 @@ pub struct Geometry {
     pub y: i64,
 }
 
+impl Geometry {
+    pub fn center(&self) -> (i64, i64) {
+        (self.x + self.width / 2, self.y + self.height / 2)
+    }
+
+    pub fn translated(&self, dx: i64, dy: i64) -> Geometry {
+        Geometry {
+            x: self.x + dx,
+            y: self.y + dy,
+            ..*self
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct WSGeometry {
     pub grid_width: i64,
@@ -112,7 +220,8 @@ pub struct WSGeometry {
     pub y: i64,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default)]
+#[serde(default)]
 pub struct Size {
     pub height: i64,
     pub width: i64,
@@ -124,20 +233,111 @@ pub struct TargetWorkspace {
     pub workspace_set: Option<WorkspaceSet>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Output {
+    #[serde(default)]
     pub geometry: Geometry,
     pub id: i64,
     pub name: String,
-    #[serde(rename = "workarea")]
+    #[serde(rename = "workarea", default)]
     pub work_area: Geometry,
-    #[serde(rename = "workspace")]
+    #[serde(rename = "workspace", default)]
     pub workspace: Workspace,
-    #[serde(rename = "wset-index")]
+    #[serde(rename = "wset-index", default = "no_id")]
     pub wset_index: i64,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(try_from = "String", into = "String")]
+pub enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::Left,
+        Direction::Right,
+        Direction::Up,
+        Direction::Down,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Direction::Left => "left",
+            Direction::Right => "right",
+            Direction::Up => "up",
+            Direction::Down => "down",
+        }
+    }
+
+    // Unit step in screen coordinates, where y grows downwards.
+    pub fn delta(&self) -> (i64, i64) {
+        match self {
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+            Direction::Up => (0, -1),
+            Direction::Down => (0, 1),
+        }
+    }
+
+    pub fn opposite(&self) -> Direction {
+        match self {
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+        }
+    }
+}
+
+impl std::fmt::Display for Direction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl std::str::FromStr for Direction {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "left" | "l" | "west" => Ok(Direction::Left),
+            "right" | "r" | "east" => Ok(Direction::Right),
+            "up" | "u" | "north" => Ok(Direction::Up),
+            "down" | "d" | "south" => Ok(Direction::Down),
+            _ => Err(ParseError(format!("invalid direction: {}", s))),
+        }
+    }
+}
+
+impl TryFrom<String> for Direction {
+    type Error = ParseError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Direction> for String {
+    fn from(value: Direction) -> Self {
+        value.to_string()
+    }
+}
+
+// A position in an output's workspace grid.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct WorkspacePosition {
+    pub x: i64,
+    pub y: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
 pub struct Workspace {
     #[serde(rename = "grid_height")]
     pub grid_height: i64,
@@ -147,6 +347,73 @@ pub struct Workspace {
     pub y: i64,
 }
 
+impl Workspace {
+    pub fn position(&self) -> WorkspacePosition {
+        WorkspacePosition {
+            x: self.x,
+            y: self.y,
+        }
+    }
+
+    pub fn count(&self) -> i64 {
+        self.grid_width * self.grid_height
+    }
+
+    pub fn contains(&self, position: WorkspacePosition) -> bool {
+        (0..self.grid_width).contains(&position.x) && (0..self.grid_height).contains(&position.y)
+    }
+
+    // Workspaces are numbered row by row, starting with 0 at the top left.
+    pub fn index_of(&self, position: WorkspacePosition) -> Option<i64> {
+        self.contains(position)
+            .then(|| position.y * self.grid_width + position.x)
+    }
+
+    pub fn position_of(&self, index: i64) -> Option<WorkspacePosition> {
+        (0..self.count())
+            .contains(&index)
+            .then(|| WorkspacePosition {
+                x: index % self.grid_width,
+                y: index / self.grid_width,
+            })
+    }
+
+    pub fn index(&self) -> i64 {
+        self.y * self.grid_width + self.x
+    }
+
+    // The workspace next to the current one, or `None` at the edge of the
+    // grid unless `wrap` is set.
+    pub fn neighbour(&self, direction: Direction, wrap: bool) -> Option<WorkspacePosition> {
+        let (dx, dy) = direction.delta();
+        let position = WorkspacePosition {
+            x: self.x + dx,
+            y: self.y + dy,
+        };
+
+        if self.contains(position) {
+            Some(position)
+        } else if wrap && self.count() > 0 {
+            Some(WorkspacePosition {
+                x: position.x.rem_euclid(self.grid_width),
+                y: position.y.rem_euclid(self.grid_height),
+            })
+        } else {
+            None
+        }
+    }
+
+    // The workspace `offset` steps away in index order.
+    pub fn step(&self, offset: i64, wrap: bool) -> Option<WorkspacePosition> {
+        let index = self.index() + offset;
+        if wrap && self.count() > 0 {
+            self.position_of(index.rem_euclid(self.count()))
+        } else {
+            self.position_of(index)
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct WorkspaceSet {
     #[serde(rename = "index")]
@@ -160,3 +427,775 @@ pub struct WorkspaceSet {
     #[serde(rename = "workspace")]
     pub workspace: Workspace,
 }
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError(pub String);
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+// Returned (wrapped in an `io::Error` of kind `Unsupported`) by methods whose
+// Wayfire plugin isn't loaded, before anything is sent to the compositor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PluginNotLoaded(pub String);
+
+impl std::fmt::Display for PluginNotLoaded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "plugin not loaded: {}", self.0)
+    }
+}
+
+impl std::error::Error for PluginNotLoaded {}
+
+impl From<PluginNotLoaded> for std::io::Error {
+    fn from(value: PluginNotLoaded) -> Self {
+        std::io::Error::new(std::io::ErrorKind::Unsupported, value)
+    }
+}
+
+// An IPC method each plugin registers while it is loaded, used to tell whether
+// a plugin listed in `core/plugins` is actually running.
+pub const PLUGIN_PROBES: [(&str, &str); 9] = [
+    ("alpha", "wf/alpha/set-view-alpha"),
+    ("cube", "cube/activate"),
+    ("expo", "expo/toggle"),
+    ("grid", "grid/slot_c"),
+    ("scale", "scale/toggle"),
+    ("simple-tile", "simple-tile/get-layout"),
+    ("vswitch", "vswitch/set-workspace"),
+    ("wm-actions", "wm-actions/set-minimized"),
+    ("wsets", "wsets/set-output-wset"),
+];
+
+// What the running compositor supports. Either list is `None` when it couldn't
+// be read, in which case every plugin or method is assumed to be available.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Capabilities {
+    pub plugins: Option<Vec<String>>,
+    pub methods: Option<Vec<String>>,
+}
+
+impl Capabilities {
+    // Parses the `core/plugins` option, which lists plugins by name or by the
+    // path of their shared object.
+    pub fn parse_plugin_list(value: &str) -> Vec<String> {
+        value
+            .split_whitespace()
+            .map(|entry| Capabilities::plugin_name(entry).to_string())
+            .collect()
+    }
+
+    // The plugin name of a `core/plugins` entry, e.g. `alpha` for
+    // `/usr/lib/wayfire/libalpha.so`.
+    pub fn plugin_name(entry: &str) -> &str {
+        let name = entry.rsplit('/').next().unwrap_or(entry);
+        let name = name.strip_suffix(".so").unwrap_or(name);
+        name.strip_prefix("lib").unwrap_or(name)
+    }
+
+    pub fn has_plugin(&self, plugin: &str) -> bool {
+        self.plugins
+            .as_ref()
+            .is_none_or(|plugins| plugins.iter().any(|p| p == plugin))
+    }
+
+    // Whether the plugin is running. Plugins with a probe method are checked
+    // against `list-methods`, which also covers plugins loaded without being
+    // listed in `core/plugins`; the others against `core/plugins` alone.
+    pub fn is_plugin_available(&self, plugin: &str) -> bool {
+        match (Capabilities::probe_method(plugin), &self.methods) {
+            (Some(probe), Some(_)) => self.has_method(probe),
+            _ => self.has_plugin(plugin),
+        }
+    }
+
+    pub fn probe_method(plugin: &str) -> Option<&'static str> {
+        PLUGIN_PROBES
+            .iter()
+            .find(|(name, _)| *name == plugin)
+            .map(|(_, method)| *method)
+    }
+
+    pub fn has_method(&self, method: &str) -> bool {
+        self.methods
+            .as_ref()
+            .is_none_or(|methods| methods.iter().any(|m| m == method))
+    }
+}
+
+// Output modes as stored in the `output:<name>/mode` option. The refresh
+// rate is in mHz, matching Wayfire's own format (e.g. `1920x1080@60000`).
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(try_from = "String", into = "String")]
+pub enum OutputMode {
+    Auto,
+    Off,
+    Current,
+    Custom {
+        width: i64,
+        height: i64,
+        refresh: Option<i64>,
+    },
+}
+
+impl std::fmt::Display for OutputMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OutputMode::Auto => write!(f, "auto"),
+            OutputMode::Off => write!(f, "off"),
+            OutputMode::Current => write!(f, "current"),
+            OutputMode::Custom {
+                width,
+                height,
+                refresh: Some(refresh),
+            } => write!(f, "{}x{}@{}", width, height, refresh),
+            OutputMode::Custom {
+                width,
+                height,
+                refresh: None,
+            } => write!(f, "{}x{}", width, height),
+        }
+    }
+}
+
+impl std::str::FromStr for OutputMode {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        match s {
+            "auto" | "default" => return Ok(OutputMode::Auto),
+            "off" => return Ok(OutputMode::Off),
+            "current" => return Ok(OutputMode::Current),
+            _ => {}
+        }
+
+        let invalid = || ParseError(format!("invalid output mode: {}", s));
+        let (size, refresh) = match s.split_once('@') {
+            Some((size, refresh)) => (
+                size,
+                Some(refresh.trim().parse::<i64>().map_err(|_| invalid())?),
+            ),
+            None => (s, None),
+        };
+        let (width, height) = size.split_once('x').ok_or_else(invalid)?;
+
+        Ok(OutputMode::Custom {
+            width: width.trim().parse().map_err(|_| invalid())?,
+            height: height.trim().parse().map_err(|_| invalid())?,
+            refresh: refresh.filter(|r| *r > 0),
+        })
+    }
+}
+
+impl TryFrom<String> for OutputMode {
+    type Error = ParseError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<OutputMode> for String {
+    fn from(value: OutputMode) -> Self {
+        value.to_string()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(try_from = "String", into = "String")]
+pub enum OutputPosition {
+    Auto,
+    At { x: i64, y: i64 },
+}
+
+impl std::fmt::Display for OutputPosition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OutputPosition::Auto => write!(f, "auto"),
+            OutputPosition::At { x, y } => write!(f, "{},{}", x, y),
+        }
+    }
+}
+
+impl std::str::FromStr for OutputPosition {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s == "auto" || s == "default" {
+            return Ok(OutputPosition::Auto);
+        }
+
+        let invalid = || ParseError(format!("invalid output position: {}", s));
+        let (x, y) = s.split_once(',').ok_or_else(invalid)?;
+
+        Ok(OutputPosition::At {
+            x: x.trim().parse().map_err(|_| invalid())?,
+            y: y.trim().parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+impl TryFrom<String> for OutputPosition {
+    type Error = ParseError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<OutputPosition> for String {
+    fn from(value: OutputPosition) -> Self {
+        value.to_string()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(try_from = "String", into = "String")]
+pub enum OutputTransform {
+    Normal,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    Flipped,
+    Flipped90,
+    Flipped180,
+    Flipped270,
+}
+
+impl OutputTransform {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OutputTransform::Normal => "normal",
+            OutputTransform::Rotate90 => "90",
+            OutputTransform::Rotate180 => "180",
+            OutputTransform::Rotate270 => "270",
+            OutputTransform::Flipped => "flipped",
+            OutputTransform::Flipped90 => "90_flipped",
+            OutputTransform::Flipped180 => "180_flipped",
+            OutputTransform::Flipped270 => "270_flipped",
+        }
+    }
+
+    pub fn is_rotated(&self) -> bool {
+        matches!(
+            self,
+            OutputTransform::Rotate90
+                | OutputTransform::Rotate270
+                | OutputTransform::Flipped90
+                | OutputTransform::Flipped270
+        )
+    }
+}
+
+impl std::fmt::Display for OutputTransform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl std::str::FromStr for OutputTransform {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "normal" => Ok(OutputTransform::Normal),
+            "90" => Ok(OutputTransform::Rotate90),
+            "180" => Ok(OutputTransform::Rotate180),
+            "270" => Ok(OutputTransform::Rotate270),
+            "flipped" => Ok(OutputTransform::Flipped),
+            "90_flipped" | "flipped-90" => Ok(OutputTransform::Flipped90),
+            "180_flipped" | "flipped-180" => Ok(OutputTransform::Flipped180),
+            "270_flipped" | "flipped-270" => Ok(OutputTransform::Flipped270),
+            other => Err(ParseError(format!("invalid output transform: {}", other))),
+        }
+    }
+}
+
+impl TryFrom<String> for OutputTransform {
+    type Error = ParseError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<OutputTransform> for String {
+    fn from(value: OutputTransform) -> Self {
+        value.to_string()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct OutputConfig {
+    pub name: String,
+    pub mode: OutputMode,
+    pub position: OutputPosition,
+    pub scale: f64,
+    pub transform: OutputTransform,
+}
+
+impl OutputConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.mode != OutputMode::Off
+    }
+
+    // The option names and values to pass to `set_option_values`.
+    pub fn to_options(&self) -> Vec<(String, String)> {
+        let section = format!("output:{}", self.name);
+        vec![
+            (format!("{}/mode", section), self.mode.to_string()),
+            (format!("{}/position", section), self.position.to_string()),
+            (format!("{}/scale", section), self.scale.to_string()),
+            (format!("{}/transform", section), self.transform.to_string()),
+        ]
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PluginActivation {
+    pub plugin: String,
+    pub state: bool,
+    #[serde(rename = "output")]
+    pub output_id: OutputId,
+    #[serde(rename = "output-data", default)]
+    pub output: Option<Output>,
+}
+
+#[derive(Debug)]
+pub enum Event {
+    OutputAdded(Output),
+    OutputRemoved(Output),
+    ViewMapped(View),
+    ViewUnmapped(View),
+    ViewFocused(View),
+    PluginActivation(PluginActivation),
+    Other(serde_json::Value),
+}
+
+impl From<serde_json::Value> for Event {
+    fn from(value: serde_json::Value) -> Self {
+        let output = || {
+            value
+                .get("output")
+                .and_then(|o| serde_json::from_value::<Output>(o.clone()).ok())
+        };
+        let view = || {
+            value
+                .get("view")
+                .and_then(|v| serde_json::from_value::<View>(v.clone()).ok())
+        };
+
+        match value.get("event").and_then(|e| e.as_str()) {
+            Some("output-added") => match output() {
+                Some(output) => Event::OutputAdded(output),
+                None => Event::Other(value),
+            },
+            Some("output-removed") => match output() {
+                Some(output) => Event::OutputRemoved(output),
+                None => Event::Other(value),
+            },
+            Some("view-mapped") => match view() {
+                Some(view) => Event::ViewMapped(view),
+                None => Event::Other(value),
+            },
+            Some("view-unmapped") => match view() {
+                Some(view) => Event::ViewUnmapped(view),
+                None => Event::Other(value),
+            },
+            Some("view-focused") => match view() {
+                Some(view) => Event::ViewFocused(view),
+                None => Event::Other(value),
+            },
+            Some("plugin-activation-state-changed") => {
+                match serde_json::from_value(value.clone()) {
+                    Ok(activation) => Event::PluginActivation(activation),
+                    Err(_) => Event::Other(value),
+                }
+            }
+            _ => Event::Other(value),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(try_from = "String", into = "String")]
+pub enum GridSlot {
+    Center,
+    Left,
+    Right,
+    Top,
+    Bottom,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+    Restore,
+}
+
+impl GridSlot {
+    pub const ALL: [GridSlot; 10] = [
+        GridSlot::Center,
+        GridSlot::Left,
+        GridSlot::Right,
+        GridSlot::Top,
+        GridSlot::Bottom,
+        GridSlot::TopLeft,
+        GridSlot::TopRight,
+        GridSlot::BottomLeft,
+        GridSlot::BottomRight,
+        GridSlot::Restore,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GridSlot::Center => "center",
+            GridSlot::Left => "left",
+            GridSlot::Right => "right",
+            GridSlot::Top => "top",
+            GridSlot::Bottom => "bottom",
+            GridSlot::TopLeft => "top-left",
+            GridSlot::TopRight => "top-right",
+            GridSlot::BottomLeft => "bottom-left",
+            GridSlot::BottomRight => "bottom-right",
+            GridSlot::Restore => "restore",
+        }
+    }
+
+    // The grid plugin's IPC method for this slot.
+    pub fn method(&self) -> &'static str {
+        match self {
+            GridSlot::Center => "grid/slot_c",
+            GridSlot::Left => "grid/slot_l",
+            GridSlot::Right => "grid/slot_r",
+            GridSlot::Top => "grid/slot_t",
+            GridSlot::Bottom => "grid/slot_b",
+            GridSlot::TopLeft => "grid/slot_tl",
+            GridSlot::TopRight => "grid/slot_tr",
+            GridSlot::BottomLeft => "grid/slot_bl",
+            GridSlot::BottomRight => "grid/slot_br",
+            GridSlot::Restore => "grid/restore",
+        }
+    }
+
+    pub fn tiled_edges(&self) -> TiledEdges {
+        match self {
+            GridSlot::Center => TiledEdges::all(),
+            GridSlot::Left => TiledEdges::TOP | TiledEdges::BOTTOM | TiledEdges::LEFT,
+            GridSlot::Right => TiledEdges::TOP | TiledEdges::BOTTOM | TiledEdges::RIGHT,
+            GridSlot::Top => TiledEdges::TOP | TiledEdges::LEFT | TiledEdges::RIGHT,
+            GridSlot::Bottom => TiledEdges::BOTTOM | TiledEdges::LEFT | TiledEdges::RIGHT,
+            GridSlot::TopLeft => TiledEdges::TOP | TiledEdges::LEFT,
+            GridSlot::TopRight => TiledEdges::TOP | TiledEdges::RIGHT,
+            GridSlot::BottomLeft => TiledEdges::BOTTOM | TiledEdges::LEFT,
+            GridSlot::BottomRight => TiledEdges::BOTTOM | TiledEdges::RIGHT,
+            GridSlot::Restore => TiledEdges::empty(),
+        }
+    }
+
+    // The slot a view with the given tiled edges is in, or `None` if it isn't
+    // tiled at all.
+    pub fn from_tiled_edges(edges: TiledEdges) -> Option<GridSlot> {
+        GridSlot::ALL
+            .into_iter()
+            .filter(|slot| *slot != GridSlot::Restore)
+            .find(|slot| slot.tiled_edges() == edges)
+    }
+}
+
+impl std::fmt::Display for GridSlot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl std::str::FromStr for GridSlot {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalized = s
+            .trim()
+            .trim_start_matches("grid/")
+            .trim_start_matches("slot_")
+            .to_ascii_lowercase()
+            .replace(['-', '_', ' '], "");
+
+        match normalized.as_str() {
+            "c" | "center" | "centre" | "maximize" => Ok(GridSlot::Center),
+            "l" | "left" => Ok(GridSlot::Left),
+            "r" | "right" => Ok(GridSlot::Right),
+            "t" | "top" => Ok(GridSlot::Top),
+            "b" | "bottom" => Ok(GridSlot::Bottom),
+            "tl" | "topleft" => Ok(GridSlot::TopLeft),
+            "tr" | "topright" => Ok(GridSlot::TopRight),
+            "bl" | "bottomleft" => Ok(GridSlot::BottomLeft),
+            "br" | "bottomright" => Ok(GridSlot::BottomRight),
+            "restore" => Ok(GridSlot::Restore),
+            _ => Err(ParseError(format!("invalid grid slot: {}", s))),
+        }
+    }
+}
+
+impl TryFrom<String> for GridSlot {
+    type Error = ParseError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<GridSlot> for String {
+    fn from(value: GridSlot) -> Self {
+        value.to_string()
+    }
+}
+
+// Edges of the output a view is tiled against, using the wlroots edge values
+// Wayfire reports in `tiled-edges`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[serde(transparent)]
+pub struct TiledEdges(u32);
+
+bitflags::bitflags! {
+    impl TiledEdges: u32 {
+        const TOP = 1;
+        const BOTTOM = 2;
+        const LEFT = 4;
+        const RIGHT = 8;
+    }
+}
+
+impl TiledEdges {
+    pub fn is_tiled(&self) -> bool {
+        !self.is_empty()
+    }
+
+    pub fn is_maximized(&self) -> bool {
+        self.is_all()
+    }
+
+    // Tiled to one half of the output: three edges, e.g. the left half is
+    // tiled against the top, bottom and left edges.
+    pub fn is_half_tiled(&self) -> bool {
+        self.bits().count_ones() == 3
+    }
+
+    pub fn is_quarter_tiled(&self) -> bool {
+        self.bits().count_ones() == 2
+            && !self.contains(TiledEdges::TOP | TiledEdges::BOTTOM)
+            && !self.contains(TiledEdges::LEFT | TiledEdges::RIGHT)
+    }
+
+    pub fn grid_slot(&self) -> Option<GridSlot> {
+        GridSlot::from_tiled_edges(*self)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[serde(from = "String", into = "String")]
+pub enum ViewRole {
+    Toplevel,
+    Unmanaged,
+    DesktopEnvironment,
+    #[default]
+    Unknown,
+    Other(String),
+}
+
+impl ViewRole {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ViewRole::Toplevel => "toplevel",
+            ViewRole::Unmanaged => "unmanaged",
+            ViewRole::DesktopEnvironment => "desktop-environment",
+            ViewRole::Unknown => "unknown",
+            ViewRole::Other(role) => role,
+        }
+    }
+}
+
+impl std::fmt::Display for ViewRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl From<String> for ViewRole {
+    fn from(value: String) -> Self {
+        match value.as_str() {
+            "toplevel" => ViewRole::Toplevel,
+            "unmanaged" => ViewRole::Unmanaged,
+            "desktop-environment" => ViewRole::DesktopEnvironment,
+            "unknown" => ViewRole::Unknown,
+            _ => ViewRole::Other(value),
+        }
+    }
+}
+
+impl From<ViewRole> for String {
+    fn from(value: ViewRole) -> Self {
+        value.to_string()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[serde(from = "String", into = "String")]
+pub enum ViewType {
+    Toplevel,
+    // Xwayland override-redirect surfaces (menus, tooltips, ...).
+    XOr,
+    Unmanaged,
+    Background,
+    Panel,
+    Overlay,
+    #[default]
+    Unknown,
+    Other(String),
+}
+
+impl ViewType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ViewType::Toplevel => "toplevel",
+            ViewType::XOr => "x-or",
+            ViewType::Unmanaged => "unmanaged",
+            ViewType::Background => "background",
+            ViewType::Panel => "panel",
+            ViewType::Overlay => "overlay",
+            ViewType::Unknown => "unknown",
+            ViewType::Other(type_field) => type_field,
+        }
+    }
+}
+
+impl std::fmt::Display for ViewType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl From<String> for ViewType {
+    fn from(value: String) -> Self {
+        match value.as_str() {
+            "toplevel" => ViewType::Toplevel,
+            "x-or" => ViewType::XOr,
+            "unmanaged" => ViewType::Unmanaged,
+            "background" => ViewType::Background,
+            "panel" => ViewType::Panel,
+            "overlay" => ViewType::Overlay,
+            "unknown" => ViewType::Unknown,
+            _ => ViewType::Other(value),
+        }
+    }
+}
+
+impl From<ViewType> for String {
+    fn from(value: ViewType) -> Self {
+        value.to_string()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[serde(from = "String", into = "String")]
+pub enum ViewLayer {
+    Background,
+    Bottom,
+    Workspace,
+    Top,
+    Unmanaged,
+    Overlay,
+    Lock,
+    DesktopWidget,
+    #[default]
+    None,
+    Other(String),
+}
+
+impl ViewLayer {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ViewLayer::Background => "background",
+            ViewLayer::Bottom => "bottom",
+            ViewLayer::Workspace => "workspace",
+            ViewLayer::Top => "top",
+            ViewLayer::Unmanaged => "unmanaged",
+            ViewLayer::Overlay => "overlay",
+            ViewLayer::Lock => "lock",
+            ViewLayer::DesktopWidget => "dew",
+            ViewLayer::None => "none",
+            ViewLayer::Other(layer) => layer,
+        }
+    }
+
+    // Position in Wayfire's scenegraph, from the bottom-most layer up. Views
+    // without a layer, or in a layer unknown to this crate, have none.
+    pub fn stacking_order(&self) -> Option<u8> {
+        match self {
+            ViewLayer::Background => Some(0),
+            ViewLayer::Bottom => Some(1),
+            ViewLayer::Workspace => Some(2),
+            ViewLayer::Top => Some(3),
+            ViewLayer::Unmanaged => Some(4),
+            ViewLayer::Overlay => Some(5),
+            ViewLayer::Lock => Some(6),
+            ViewLayer::DesktopWidget => Some(7),
+            ViewLayer::None | ViewLayer::Other(_) => None,
+        }
+    }
+
+    pub fn is_above(&self, other: &ViewLayer) -> bool {
+        matches!(
+            (self.stacking_order(), other.stacking_order()),
+            (Some(a), Some(b)) if a > b
+        )
+    }
+
+    pub fn is_below(&self, other: &ViewLayer) -> bool {
+        other.is_above(self)
+    }
+
+    // Layers drawn below regular windows, like wallpapers and desktop icons.
+    pub fn is_below_workspace(&self) -> bool {
+        self.is_below(&ViewLayer::Workspace)
+    }
+
+    // Layers drawn on top of regular windows, like panels and lock screens.
+    pub fn is_above_workspace(&self) -> bool {
+        self.is_above(&ViewLayer::Workspace)
+    }
+}
+
+impl std::fmt::Display for ViewLayer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl From<String> for ViewLayer {
+    fn from(value: String) -> Self {
+        match value.as_str() {
+            "background" => ViewLayer::Background,
+            "bottom" => ViewLayer::Bottom,
+            "workspace" => ViewLayer::Workspace,
+            "top" => ViewLayer::Top,
+            "unmanaged" => ViewLayer::Unmanaged,
+            "overlay" => ViewLayer::Overlay,
+            "lock" => ViewLayer::Lock,
+            "dew" => ViewLayer::DesktopWidget,
+            "none" => ViewLayer::None,
+            _ => ViewLayer::Other(value),
+        }
+    }
+}
+
+impl From<ViewLayer> for String {
+    fn from(value: ViewLayer) -> Self {
+        value.to_string()
+    }
+}
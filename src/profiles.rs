@@ -0,0 +1,517 @@
+use crate::ipc::WayfireSocket;
+use crate::models::{Event, Output, OutputMode, OutputPosition, OutputTransform};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// Declarative output profiles, in the spirit of kanshi:
+//
+//     [[profile]]
+//     name = "docked"
+//
+//     [[profile.output]]
+//     name = "eDP-1"
+//     enabled = false
+//
+//     [[profile.output]]
+//     name = "DP-*"
+//     mode = "2560x1440@59951"
+//     position = "0,0"
+//     scale = 1.0
+//     wset = 1
+//
+// A profile matches when every connected output is claimed by exactly one of
+// its output entries. Output names may contain `*` wildcards.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ProfileConfig {
+    #[serde(rename = "profile", default)]
+    pub profiles: Vec<Profile>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Profile {
+    pub name: String,
+    #[serde(rename = "output", default)]
+    pub outputs: Vec<OutputProfile>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OutputProfile {
+    pub name: String,
+    #[serde(default)]
+    pub enabled: Option<bool>,
+    #[serde(default)]
+    pub mode: Option<OutputMode>,
+    #[serde(default)]
+    pub position: Option<OutputPosition>,
+    #[serde(default)]
+    pub scale: Option<f64>,
+    #[serde(default)]
+    pub transform: Option<OutputTransform>,
+    #[serde(default)]
+    pub wset: Option<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProfileAction {
+    SetOption {
+        option: String,
+        value: String,
+    },
+    AssignWset {
+        output_id: i64,
+        output_name: String,
+        wset: i64,
+    },
+}
+
+impl ProfileAction {
+    // The output the action applies to.
+    pub fn output_name(&self) -> &str {
+        match self {
+            ProfileAction::SetOption { option, .. } => option
+                .strip_prefix("output:")
+                .and_then(|rest| rest.split_once('/'))
+                .map_or(option.as_str(), |(name, _)| name),
+            ProfileAction::AssignWset { output_name, .. } => output_name,
+        }
+    }
+}
+
+// An output whose part of a profile couldn't be applied.
+#[derive(Debug)]
+pub struct OutputFailure {
+    pub output: String,
+    pub error: io::Error,
+}
+
+impl fmt::Display for ProfileAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProfileAction::SetOption { option, value } => write!(f, "set {} = {}", option, value),
+            ProfileAction::AssignWset {
+                output_id,
+                output_name,
+                wset,
+            } => write!(
+                f,
+                "assign wset {} to {} (id {})",
+                wset, output_name, output_id
+            ),
+        }
+    }
+}
+
+impl ProfileConfig {
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        Self::parse(&contents)
+    }
+
+    pub fn parse(contents: &str) -> io::Result<Self> {
+        toml::from_str(contents).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Failed to parse profiles: {}", e),
+            )
+        })
+    }
+
+    // Returns the first profile matching the given set of output names,
+    // along with the output entry each name was matched against.
+    pub fn find_match<'a>(
+        &'a self,
+        output_names: &[String],
+    ) -> Option<(&'a Profile, Vec<(String, &'a OutputProfile)>)> {
+        self.profiles
+            .iter()
+            .find_map(|profile| profile.match_outputs(output_names).map(|m| (profile, m)))
+    }
+}
+
+impl Profile {
+    pub fn match_outputs(&self, output_names: &[String]) -> Option<Vec<(String, &OutputProfile)>> {
+        if self.outputs.len() != output_names.len() {
+            return None;
+        }
+
+        let mut assignment = vec![None; self.outputs.len()];
+        if !assign_outputs(&self.outputs, output_names, 0, &mut assignment) {
+            return None;
+        }
+
+        Some(
+            assignment
+                .into_iter()
+                .zip(&self.outputs)
+                .map(|(name, output)| (output_names[name.unwrap()].clone(), output))
+                .collect(),
+        )
+    }
+}
+
+// Backtracking assignment of profile entries to connected outputs, so that a
+// wildcard entry doesn't steal an output an exact entry needs.
+fn assign_outputs(
+    entries: &[OutputProfile],
+    names: &[String],
+    entry: usize,
+    assignment: &mut Vec<Option<usize>>,
+) -> bool {
+    if entry == entries.len() {
+        return true;
+    }
+
+    for (index, name) in names.iter().enumerate() {
+        if assignment.contains(&Some(index)) || !glob_match(&entries[entry].name, name) {
+            continue;
+        }
+
+        assignment[entry] = Some(index);
+        if assign_outputs(entries, names, entry + 1, assignment) {
+            return true;
+        }
+        assignment[entry] = None;
+    }
+
+    false
+}
+
+fn glob_match(pattern: &str, name: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == name,
+        Some((prefix, rest)) => {
+            let Some(remaining) = name.strip_prefix(prefix) else {
+                return false;
+            };
+            (0..=remaining.len())
+                .filter(|i| remaining.is_char_boundary(*i))
+                .any(|i| glob_match(rest, &remaining[i..]))
+        }
+    }
+}
+
+// Builds the list of actions needed to apply the matched profile entries.
+pub fn plan(matched: &[(String, &OutputProfile)], outputs: &[Output]) -> Vec<ProfileAction> {
+    let mut actions = Vec::new();
+
+    for (name, profile) in matched {
+        let option = |key: &str| format!("output:{}/{}", name, key);
+
+        let mode = match (profile.enabled, profile.mode) {
+            (Some(false), _) => Some(OutputMode::Off),
+            (_, Some(mode)) => Some(mode),
+            (Some(true), None) => Some(OutputMode::Auto),
+            (None, None) => None,
+        };
+
+        if let Some(mode) = mode {
+            actions.push(ProfileAction::SetOption {
+                option: option("mode"),
+                value: mode.to_string(),
+            });
+        }
+
+        if mode == Some(OutputMode::Off) {
+            continue;
+        }
+
+        if let Some(position) = profile.position {
+            actions.push(ProfileAction::SetOption {
+                option: option("position"),
+                value: position.to_string(),
+            });
+        }
+
+        if let Some(scale) = profile.scale {
+            actions.push(ProfileAction::SetOption {
+                option: option("scale"),
+                value: scale.to_string(),
+            });
+        }
+
+        if let Some(transform) = profile.transform {
+            actions.push(ProfileAction::SetOption {
+                option: option("transform"),
+                value: transform.to_string(),
+            });
+        }
+
+        if let Some(wset) = profile.wset {
+            // Outputs that are currently disabled have no id yet; their wset
+            // is assigned once they show up again.
+            if let Some(output) = outputs.iter().find(|o| &o.name == name) {
+                if output.wset_index != wset {
+                    actions.push(ProfileAction::AssignWset {
+                        output_id: output.id,
+                        output_name: name.clone(),
+                        wset,
+                    });
+                }
+            }
+        }
+    }
+
+    actions
+}
+
+// Applies the actions output by output, so one output rejecting a setting
+// doesn't keep the others from being configured. Returns the outputs that
+// failed.
+pub async fn apply(socket: &mut WayfireSocket, actions: &[ProfileAction]) -> Vec<OutputFailure> {
+    let mut output_names: Vec<&str> = Vec::new();
+    for action in actions {
+        if !output_names.contains(&action.output_name()) {
+            output_names.push(action.output_name());
+        }
+    }
+
+    let mut failures = Vec::new();
+    for name in output_names {
+        if let Err(error) = apply_output(socket, actions, name).await {
+            failures.push(OutputFailure {
+                output: name.to_string(),
+                error,
+            });
+        }
+    }
+
+    failures
+}
+
+async fn apply_output(
+    socket: &mut WayfireSocket,
+    actions: &[ProfileAction],
+    output_name: &str,
+) -> io::Result<()> {
+    let actions = actions
+        .iter()
+        .filter(|action| action.output_name() == output_name);
+
+    let options: Vec<(String, String)> = actions
+        .clone()
+        .filter_map(|action| match action {
+            ProfileAction::SetOption { option, value } => Some((option.clone(), value.clone())),
+            _ => None,
+        })
+        .collect();
+
+    if !options.is_empty() {
+        socket.set_option_values(options).await?;
+    }
+
+    for action in actions {
+        if let ProfileAction::AssignWset {
+            output_id, wset, ..
+        } = action
+        {
+            socket.set_output_wset(*output_id, *wset).await?;
+        }
+    }
+
+    Ok(())
+}
+
+// The outcome of matching the connected outputs against the profiles.
+#[derive(Debug)]
+pub enum ProfileUpdate {
+    // No profile matches the connected outputs.
+    NoMatch(Vec<String>),
+    // The matching profile is already applied.
+    Unchanged(String),
+    // The matching profile was applied, or would have been in a dry run.
+    Applied {
+        profile: String,
+        actions: Vec<ProfileAction>,
+    },
+    // Some outputs rejected their settings. The profile is tried again on
+    // the next hotplug event.
+    Failed {
+        profile: String,
+        actions: Vec<ProfileAction>,
+        failures: Vec<OutputFailure>,
+    },
+}
+
+pub struct ProfileDaemon {
+    config: ProfileConfig,
+    dry_run: bool,
+    active: Option<(String, Vec<String>)>,
+    // Outputs turned off by the active profile disappear from
+    // `list_outputs`, but are still physically connected.
+    disabled: HashSet<String>,
+}
+
+impl ProfileDaemon {
+    pub fn new(config: ProfileConfig, dry_run: bool) -> Self {
+        ProfileDaemon {
+            config,
+            dry_run,
+            active: None,
+            disabled: HashSet::new(),
+        }
+    }
+
+    pub fn active_profile(&self) -> Option<&str> {
+        self.active.as_ref().map(|(name, _)| name.as_str())
+    }
+
+    // Matches the currently connected outputs and applies the first matching
+    // profile.
+    pub async fn update(&mut self, socket: &mut WayfireSocket) -> io::Result<ProfileUpdate> {
+        let outputs = socket.list_outputs().await?;
+
+        let mut names: Vec<String> = outputs.iter().map(|o| o.name.clone()).collect();
+        for name in &self.disabled {
+            if !names.contains(name) {
+                names.push(name.clone());
+            }
+        }
+        names.sort();
+
+        let Some((profile, matched)) = self.config.find_match(&names) else {
+            self.active = None;
+            self.disabled.clear();
+            return Ok(ProfileUpdate::NoMatch(names));
+        };
+
+        let mut actions = plan(&matched, &outputs);
+        if self.active.as_ref() == Some(&(profile.name.clone(), names.clone())) {
+            // The options are already applied, setting them again would only
+            // trigger another round of hotplug events.
+            actions.retain(|a| matches!(a, ProfileAction::AssignWset { .. }));
+            if actions.is_empty() {
+                return Ok(ProfileUpdate::Unchanged(profile.name.clone()));
+            }
+        }
+
+        self.disabled = matched
+            .iter()
+            .filter(|(_, output)| output.enabled == Some(false))
+            .map(|(name, _)| name.clone())
+            .collect();
+        self.active = None;
+
+        if !self.dry_run {
+            let failures = apply(socket, &actions).await;
+            if !failures.is_empty() {
+                return Ok(ProfileUpdate::Failed {
+                    profile: profile.name.clone(),
+                    actions,
+                    failures,
+                });
+            }
+        }
+
+        self.active = Some((profile.name.clone(), names));
+        Ok(ProfileUpdate::Applied {
+            profile: profile.name.clone(),
+            actions,
+        })
+    }
+
+    // Applies the matching profile, then again whenever outputs are
+    // connected or disconnected, passing each outcome to `on_update`.
+    pub async fn run(
+        &mut self,
+        socket: &mut WayfireSocket,
+        mut on_update: impl FnMut(ProfileUpdate),
+    ) -> io::Result<()> {
+        socket
+            .watch(Some(vec![
+                "output-added".to_string(),
+                "output-removed".to_string(),
+            ]))
+            .await?;
+
+        on_update(self.update(socket).await?);
+
+        loop {
+            match socket.read_event().await? {
+                Event::OutputAdded(_) | Event::OutputRemoved(_) => {
+                    on_update(self.update(socket).await?);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn glob_matches_wildcards() {
+        assert!(glob_match("eDP-1", "eDP-1"));
+        assert!(!glob_match("eDP-1", "eDP-10"));
+        assert!(glob_match("DP-*", "DP-3"));
+        assert!(glob_match("DP-*", "DP-"));
+        assert!(!glob_match("DP-*", "eDP-1"));
+        assert!(glob_match("*", "HDMI-A-1"));
+        assert!(glob_match("*-A-*", "HDMI-A-1"));
+        assert!(!glob_match("*-B-*", "HDMI-A-1"));
+        assert!(glob_match("HDMI*1", "HDMI-A-1"));
+    }
+
+    #[test]
+    fn wildcard_entries_leave_exact_names_to_exact_entries() {
+        let config = ProfileConfig::parse(
+            r#"
+            [[profile]]
+            name = "docked"
+            [[profile.output]]
+            name = "*"
+            [[profile.output]]
+            name = "eDP-1"
+            enabled = false
+            "#,
+        )
+        .unwrap();
+
+        let (profile, matched) = config.find_match(&names(&["DP-3", "eDP-1"])).unwrap();
+        assert_eq!(profile.name, "docked");
+        assert_eq!(matched[0].0, "DP-3");
+        assert_eq!(matched[1].0, "eDP-1");
+    }
+
+    #[test]
+    fn profiles_need_every_output_claimed() {
+        let config = ProfileConfig::parse(
+            r#"
+            [[profile]]
+            name = "laptop"
+            [[profile.output]]
+            name = "eDP-1"
+            "#,
+        )
+        .unwrap();
+
+        assert!(config.find_match(&names(&["eDP-1"])).is_some());
+        assert!(config.find_match(&names(&["eDP-1", "DP-3"])).is_none());
+        assert!(config.find_match(&names(&["DP-3"])).is_none());
+    }
+
+    #[test]
+    fn actions_know_their_output() {
+        let action = ProfileAction::SetOption {
+            option: "output:DP-3/scale".to_string(),
+            value: "1".to_string(),
+        };
+        assert_eq!(action.output_name(), "DP-3");
+
+        let action = ProfileAction::AssignWset {
+            output_id: 2,
+            output_name: "eDP-1".to_string(),
+            wset: 1,
+        };
+        assert_eq!(action.output_name(), "eDP-1");
+    }
+}
@@ -0,0 +1,528 @@
+use crate::ipc::WayfireSocket;
+use crate::models::{Event, Geometry, GridSlot, ParseError, View, ViewRole, WorkspacePosition};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+// Window rules applied to views as they are mapped:
+//
+//     [[rule]]
+//     name = "picture-in-picture"
+//     match = { app_id = "firefox", title = "^Picture-in-Picture$" }
+//     actions = { sticky = true, always_on_top = true }
+//
+//     [[rule]]
+//     match = { app_id = "org.telegram.desktop", has_parent = false }
+//     actions = { workspace = { x = 2, y = 0 }, slot = "right" }
+//
+// All conditions of a match must hold. Every matching rule is applied, in
+// file order. The same structure can be written in YAML. Unknown keys are
+// rejected, so a misspelled condition or action doesn't go unnoticed.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct RuleSet {
+    #[serde(rename = "rule", alias = "rules", default)]
+    pub rules: Vec<Rule>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(rename = "match", default)]
+    pub matches: ViewMatch,
+    #[serde(default)]
+    pub actions: RuleActions,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct ViewMatch {
+    #[serde(default)]
+    pub app_id: Option<String>,
+    #[serde(default)]
+    pub title: Option<Pattern>,
+    #[serde(default)]
+    pub role: Option<ViewRole>,
+    #[serde(default)]
+    pub has_parent: Option<bool>,
+    // Matches views whose parent has this app-id, e.g. dialogs of an app.
+    #[serde(default)]
+    pub parent_app_id: Option<String>,
+    #[serde(default)]
+    pub pid: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct RuleActions {
+    #[serde(default)]
+    pub alpha: Option<f64>,
+    #[serde(default)]
+    pub sticky: Option<bool>,
+    #[serde(default)]
+    pub always_on_top: Option<bool>,
+    #[serde(default)]
+    pub workspace: Option<WorkspacePosition>,
+    #[serde(default)]
+    pub geometry: Option<Geometry>,
+    // Output to move the view to along with `geometry`.
+    #[serde(default)]
+    pub output: Option<String>,
+    #[serde(default)]
+    pub slot: Option<GridSlot>,
+    #[serde(default)]
+    pub fullscreen: Option<bool>,
+}
+
+// A title regex, compiled when the rules are loaded.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(try_from = "String", into = "String")]
+pub struct Pattern(Regex);
+
+impl Pattern {
+    pub fn is_match(&self, text: &str) -> bool {
+        self.0.is_match(text)
+    }
+
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+impl std::str::FromStr for Pattern {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Regex::new(s)
+            .map(Pattern)
+            .map_err(|e| ParseError(format!("invalid pattern {}: {}", s, e)))
+    }
+}
+
+impl TryFrom<String> for Pattern {
+    type Error = ParseError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Pattern> for String {
+    fn from(value: Pattern) -> Self {
+        value.as_str().to_string()
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl RuleSet {
+    // Reads YAML for `.yaml`/`.yml` files and TOML otherwise.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path)?;
+
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("yaml") | Some("yml") => Self::parse_yaml(&contents),
+            _ => Self::parse_toml(&contents),
+        }
+    }
+
+    pub fn parse_toml(contents: &str) -> io::Result<Self> {
+        toml::from_str(contents).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Failed to parse rules: {}", e),
+            )
+        })
+    }
+
+    pub fn parse_yaml(contents: &str) -> io::Result<Self> {
+        serde_norway::from_str(contents).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Failed to parse rules: {}", e),
+            )
+        })
+    }
+
+    pub fn matching<'a>(
+        &'a self,
+        view: &'a View,
+        parent: Option<&'a View>,
+    ) -> impl Iterator<Item = &'a Rule> + 'a {
+        self.rules
+            .iter()
+            .filter(move |rule| rule.matches.matches(view, parent))
+    }
+}
+
+impl Rule {
+    pub fn display_name(&self) -> String {
+        self.name
+            .clone()
+            .unwrap_or_else(|| self.matches.to_string())
+    }
+}
+
+impl ViewMatch {
+    pub fn matches(&self, view: &View, parent: Option<&View>) -> bool {
+        self.app_id
+            .as_ref()
+            .is_none_or(|app_id| &view.app_id == app_id)
+            && self
+                .title
+                .as_ref()
+                .is_none_or(|title| title.is_match(&view.title))
+            && self.role.as_ref().is_none_or(|role| &view.role == role)
+            && self
+                .has_parent
+                .is_none_or(|has_parent| (view.parent >= 0) == has_parent)
+            && self
+                .parent_app_id
+                .as_ref()
+                .is_none_or(|app_id| parent.is_some_and(|p| &p.app_id == app_id))
+            && self.pid.is_none_or(|pid| view.pid == pid)
+    }
+}
+
+impl fmt::Display for ViewMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut conditions = Vec::new();
+        if let Some(app_id) = &self.app_id {
+            conditions.push(format!("app_id={}", app_id));
+        }
+        if let Some(title) = &self.title {
+            conditions.push(format!("title~{}", title));
+        }
+        if let Some(role) = &self.role {
+            conditions.push(format!("role={}", role));
+        }
+        if let Some(has_parent) = self.has_parent {
+            conditions.push(format!("has_parent={}", has_parent));
+        }
+        if let Some(app_id) = &self.parent_app_id {
+            conditions.push(format!("parent_app_id={}", app_id));
+        }
+        if let Some(pid) = self.pid {
+            conditions.push(format!("pid={}", pid));
+        }
+
+        if conditions.is_empty() {
+            write!(f, "any view")
+        } else {
+            write!(f, "{}", conditions.join(", "))
+        }
+    }
+}
+
+impl RuleActions {
+    pub async fn apply(&self, socket: &mut WayfireSocket, view: &View) -> io::Result<()> {
+        // Moving the view comes first, so the other actions aren't undone by
+        // the move (e.g. a slot on the wrong workspace).
+        if let Some(workspace) = self.workspace {
+            socket
+                .send_view_to_workspace(view.id, workspace.x, workspace.y)
+                .await?;
+        }
+
+        if let Some(geometry) = self.geometry {
+            let output_id = match &self.output {
+                Some(name) => {
+                    let outputs = socket.list_outputs().await?;
+                    let output = outputs.iter().find(|o| &o.name == name).ok_or_else(|| {
+                        io::Error::new(
+                            io::ErrorKind::NotFound,
+                            format!("Output not found: {}", name),
+                        )
+                    })?;
+                    Some(output.id)
+                }
+                None => None,
+            };
+
+            socket
+                .configure_view(
+                    view.id,
+                    geometry.x,
+                    geometry.y,
+                    geometry.width,
+                    geometry.height,
+                    output_id,
+                )
+                .await?;
+        }
+
+        if let Some(slot) = self.slot {
+            socket.assign_slot(view.id, slot).await?;
+        }
+
+        if let Some(fullscreen) = self.fullscreen {
+            socket.set_view_fullscreen(view.id, fullscreen).await?;
+        }
+
+        if let Some(sticky) = self.sticky {
+            socket.set_view_sticky(view.id, sticky).await?;
+        }
+
+        if let Some(always_on_top) = self.always_on_top {
+            socket
+                .set_view_always_on_top(view.id, always_on_top)
+                .await?;
+        }
+
+        if let Some(alpha) = self.alpha {
+            socket.set_view_alpha(view.id, alpha).await?;
+        }
+
+        Ok(())
+    }
+}
+
+// Applies a rule set to newly mapped views. When loaded from a file, the file
+// is reloaded whenever it changed, checked at least every `RELOAD_INTERVAL`.
+pub struct RuleEngine {
+    rules: RuleSet,
+    path: Option<PathBuf>,
+    modified: Option<SystemTime>,
+}
+
+const RELOAD_INTERVAL: Duration = Duration::from_secs(1);
+
+// What `RuleEngine::run` did, for reporting.
+#[derive(Debug)]
+pub enum RuleEvent {
+    // The rule file changed and this many rules were loaded from it.
+    Reloaded(usize),
+    ReloadFailed(io::Error),
+    Applied {
+        view: View,
+        rule: String,
+    },
+    Failed {
+        view: View,
+        rule: String,
+        error: io::Error,
+    },
+}
+
+impl RuleEngine {
+    pub fn new(rules: RuleSet) -> Self {
+        RuleEngine {
+            rules,
+            path: None,
+            modified: None,
+        }
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let modified = fs::metadata(&path)?.modified().ok();
+
+        Ok(RuleEngine {
+            rules: RuleSet::load(&path)?,
+            path: Some(path),
+            modified,
+        })
+    }
+
+    pub fn rules(&self) -> &RuleSet {
+        &self.rules
+    }
+
+    // Reloads the rules if the file changed since it was last read. A file
+    // that fails to parse keeps the previous rules in place and is reported.
+    pub fn reload_if_changed(&mut self) -> io::Result<bool> {
+        let Some(path) = &self.path else {
+            return Ok(false);
+        };
+
+        // A removed file counts as a change once, and is reported by `load`.
+        let modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+        if modified == self.modified {
+            return Ok(false);
+        }
+
+        self.modified = modified;
+        self.rules = RuleSet::load(path)?;
+        Ok(true)
+    }
+
+    // Applies every matching rule to the view and returns each rule's name
+    // with its outcome. A failing rule doesn't keep the others from applying.
+    pub async fn apply(
+        &self,
+        socket: &mut WayfireSocket,
+        view: &View,
+    ) -> Vec<(String, io::Result<()>)> {
+        let parent = if view.parent >= 0 {
+            socket.get_view(view.parent).await.ok()
+        } else {
+            None
+        };
+
+        let mut outcomes = Vec::new();
+        for rule in self.rules.matching(view, parent.as_ref()) {
+            let result = rule.actions.apply(socket, view).await;
+            outcomes.push((rule.display_name(), result));
+        }
+
+        outcomes
+    }
+
+    // Applies the rules to every view mapped from now on, reporting what was
+    // done to `on_event`. Failing actions and rule files that fail to parse
+    // don't stop the engine.
+    pub async fn run(
+        &mut self,
+        socket: &mut WayfireSocket,
+        mut on_event: impl FnMut(RuleEvent),
+    ) -> io::Result<()> {
+        socket.watch(Some(vec!["view-mapped".to_string()])).await?;
+
+        loop {
+            let event = socket.read_event_timeout(RELOAD_INTERVAL).await?;
+
+            match self.reload_if_changed() {
+                Ok(true) => on_event(RuleEvent::Reloaded(self.rules.rules.len())),
+                Ok(false) => {}
+                Err(e) => on_event(RuleEvent::ReloadFailed(e)),
+            }
+
+            let Some(Event::ViewMapped(view)) = event else {
+                continue;
+            };
+
+            for (rule, result) in self.apply(socket, &view).await {
+                let view = view.clone();
+                match result {
+                    Ok(()) => on_event(RuleEvent::Applied { view, rule }),
+                    Err(error) => on_event(RuleEvent::Failed { view, rule, error }),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn view(id: i64, app_id: &str, title: &str, parent: i64) -> View {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "app-id": app_id,
+            "title": title,
+            "parent": parent,
+            "pid": 1000 + id,
+            "role": "toplevel",
+        }))
+        .unwrap()
+    }
+
+    const RULES: &str = r#"
+        [[rule]]
+        name = "pip"
+        match = { app_id = "firefox", title = "^Picture-in-Picture$" }
+
+        [rule.actions]
+        sticky = true
+        geometry = { x = 1500, y = 40, width = 400, height = 225 }
+
+        [[rule]]
+        match = { parent_app_id = "gimp", role = "toplevel" }
+        actions = { alpha = 0.9 }
+
+        [[rule]]
+        match = { has_parent = false, pid = 1003 }
+    "#;
+
+    #[test]
+    fn parses_nested_actions() {
+        let rules = RuleSet::parse_toml(RULES).unwrap();
+        assert_eq!(rules.rules.len(), 3);
+
+        let pip = &rules.rules[0].actions;
+        assert_eq!(pip.sticky, Some(true));
+        assert_eq!(pip.geometry.map(|g| g.width), Some(400));
+        assert_eq!(rules.rules[1].actions.alpha, Some(0.9));
+        assert!(rules.rules[2].actions.alpha.is_none());
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        let misspelled_action =
+            "[[rule]]\nmatch = { app_id = \"foot\" }\nactions = { stiky = true }";
+        let misspelled_condition = "[[rule]]\nmatch = { appid = \"foot\" }";
+        let flat_action = "[[rule]]\nmatch = { app_id = \"foot\" }\nsticky = true";
+
+        for contents in [misspelled_action, misspelled_condition, flat_action] {
+            let error = RuleSet::parse_toml(contents).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        }
+        assert!(RuleSet::parse_toml("[[rule]]\nmatch = { title = \"(\" }").is_err());
+    }
+
+    #[test]
+    fn parses_yaml() {
+        let rules = RuleSet::parse_yaml(
+            "rules:\n  - match: { app_id: foot }\n    actions: { workspace: { x: 1, y: 0 } }\n",
+        )
+        .unwrap();
+        assert_eq!(rules.rules[0].matches.app_id.as_deref(), Some("foot"));
+        assert!(rules.rules[0].actions.workspace.is_some());
+
+        assert!(
+            RuleSet::parse_yaml("rules:\n  - match: { app_id: foot }\n    alpha: 0.5\n").is_err()
+        );
+    }
+
+    #[test]
+    fn matches_views_on_every_condition() {
+        let rules = RuleSet::parse_toml(RULES).unwrap();
+        let names = |view: &View, parent: Option<&View>| -> Vec<String> {
+            rules
+                .matching(view, parent)
+                .map(Rule::display_name)
+                .collect()
+        };
+
+        let pip = view(1, "firefox", "Picture-in-Picture", -1);
+        assert_eq!(names(&pip, None), ["pip"]);
+        assert!(names(
+            &view(1, "firefox", "Picture-in-Picture - Mozilla", -1),
+            None
+        )
+        .is_empty());
+
+        let gimp = view(2, "gimp", "GIMP", -1);
+        let dialog = view(3, "gimp", "Export Image", 2);
+        assert_eq!(
+            names(&dialog, Some(&gimp)),
+            ["role=toplevel, parent_app_id=gimp"]
+        );
+        assert!(names(&dialog, None).is_empty());
+
+        let top_level = view(3, "foot", "term", -1);
+        assert_eq!(names(&top_level, None), ["has_parent=false, pid=1003"]);
+    }
+
+    #[test]
+    fn empty_match_matches_any_view() {
+        let rules = RuleSet::parse_toml("[[rule]]\nactions = { sticky = true }").unwrap();
+        assert_eq!(rules.rules[0].display_name(), "any view");
+        assert_eq!(
+            rules.matching(&view(1, "foot", "term", -1), None).count(),
+            1
+        );
+    }
+}
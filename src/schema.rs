@@ -0,0 +1,386 @@
+use crate::config::{Activator, AnimationDuration, ButtonBinding, Color, KeyBinding};
+use crate::models::{OutputMode, OutputPosition, ParseError};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// Option schemas read from Wayfire's plugin metadata, the XML files installed
+// in `WayfireConfiguration.plugin_xml_dir` (usually /usr/share/wayfire/metadata).
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ConfigSchema {
+    pub plugins: Vec<PluginSchema>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct PluginSchema {
+    pub name: String,
+    pub short: Option<String>,
+    pub long: Option<String>,
+    pub category: Option<String>,
+    pub options: Vec<OptionSchema>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OptionSchema {
+    pub name: String,
+    pub option_type: OptionType,
+    pub short: Option<String>,
+    pub long: Option<String>,
+    pub default: Option<String>,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub precision: Option<f64>,
+    // Named values for `int` options that are really enumerations.
+    pub choices: Vec<OptionChoice>,
+    pub group: Option<String>,
+    pub subgroup: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct OptionChoice {
+    pub value: String,
+    pub name: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[serde(from = "String", into = "String")]
+pub enum OptionType {
+    Bool,
+    Int,
+    Double,
+    String,
+    Key,
+    Button,
+    Gesture,
+    Activator,
+    Color,
+    Animation,
+    OutputMode,
+    OutputPosition,
+    DynamicList,
+    Other(String),
+}
+
+impl OptionType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            OptionType::Bool => "bool",
+            OptionType::Int => "int",
+            OptionType::Double => "double",
+            OptionType::String => "string",
+            OptionType::Key => "key",
+            OptionType::Button => "button",
+            OptionType::Gesture => "gesture",
+            OptionType::Activator => "activator",
+            OptionType::Color => "color",
+            OptionType::Animation => "animation",
+            OptionType::OutputMode => "output::mode",
+            OptionType::OutputPosition => "output::position",
+            OptionType::DynamicList => "dynamic-list",
+            OptionType::Other(option_type) => option_type,
+        }
+    }
+}
+
+impl fmt::Display for OptionType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl From<String> for OptionType {
+    fn from(value: String) -> Self {
+        match value.as_str() {
+            "bool" => OptionType::Bool,
+            "int" => OptionType::Int,
+            "double" => OptionType::Double,
+            "string" => OptionType::String,
+            "key" => OptionType::Key,
+            "button" => OptionType::Button,
+            "gesture" => OptionType::Gesture,
+            "activator" => OptionType::Activator,
+            "color" => OptionType::Color,
+            "animation" => OptionType::Animation,
+            "output::mode" => OptionType::OutputMode,
+            "output::position" => OptionType::OutputPosition,
+            "dynamic-list" => OptionType::DynamicList,
+            _ => OptionType::Other(value),
+        }
+    }
+}
+
+impl From<OptionType> for String {
+    fn from(value: OptionType) -> Self {
+        value.to_string()
+    }
+}
+
+impl ConfigSchema {
+    // Reads every `*.xml` file in the given directory.
+    pub fn load_dir<P: AsRef<Path>>(dir: P) -> io::Result<Self> {
+        let mut paths: Vec<_> = fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "xml"))
+            .collect();
+        paths.sort();
+
+        let mut schema = ConfigSchema::default();
+        for path in paths {
+            let contents = fs::read_to_string(&path)?;
+            let plugins = parse_metadata(&contents).map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{}: {}", path.display(), e),
+                )
+            })?;
+            schema.plugins.extend(plugins);
+        }
+
+        Ok(schema)
+    }
+
+    pub fn parse(xml: &str) -> Result<Self, ParseError> {
+        Ok(ConfigSchema {
+            plugins: parse_metadata(xml)?,
+        })
+    }
+
+    pub fn plugin(&self, name: &str) -> Option<&PluginSchema> {
+        self.plugins.iter().find(|plugin| plugin.name == name)
+    }
+
+    // Looks up an option by its full name, e.g. `core/plugins`. Per-output
+    // and per-device sections like `output:DP-1` use the `output` schema.
+    pub fn option(&self, option: &str) -> Option<&OptionSchema> {
+        let (section, name) = option.split_once('/')?;
+        let section = section.split(':').next().unwrap_or(section);
+        self.plugin(section)?.option(name)
+    }
+
+    pub fn validate(&self, option: &str, value: &str) -> Result<(), ParseError> {
+        self.option(option)
+            .ok_or_else(|| ParseError(format!("unknown option: {}", option)))?
+            .validate(value)
+            .map_err(|e| ParseError(format!("{}: {}", option, e)))
+    }
+}
+
+impl PluginSchema {
+    pub fn option(&self, name: &str) -> Option<&OptionSchema> {
+        self.options.iter().find(|option| option.name == name)
+    }
+
+    // Group names in the order they appear in the metadata.
+    pub fn groups(&self) -> Vec<&str> {
+        let mut groups = Vec::new();
+        for group in self.options.iter().filter_map(|o| o.group.as_deref()) {
+            if !groups.contains(&group) {
+                groups.push(group);
+            }
+        }
+        groups
+    }
+
+    pub fn options_in_group<'a>(
+        &'a self,
+        group: Option<&'a str>,
+    ) -> impl Iterator<Item = &'a OptionSchema> + 'a {
+        self.options
+            .iter()
+            .filter(move |option| option.group.as_deref() == group)
+    }
+}
+
+// Help text listing the plugin's options, grouped as in the metadata.
+impl fmt::Display for PluginSchema {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        if let Some(short) = &self.short {
+            write!(f, " - {}", short)?;
+        }
+        writeln!(f)?;
+        if let Some(long) = &self.long {
+            writeln!(f, "  {}", long)?;
+        }
+
+        let groups = std::iter::once(None).chain(self.groups().into_iter().map(Some));
+        for group in groups {
+            let mut options = self.options_in_group(group).peekable();
+            if options.peek().is_none() {
+                continue;
+            }
+
+            if let Some(group) = group {
+                writeln!(f, "\n  [{}]", group)?;
+            }
+            for option in options {
+                writeln!(f, "  {}", option)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for OptionSchema {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.name, self.option_type)?;
+        if let Some(short) = &self.short {
+            write!(f, ": {}", short)?;
+        }
+        if let Some(default) = &self.default {
+            write!(f, " [default: {}]", default)?;
+        }
+        match (self.min, self.max) {
+            (Some(min), Some(max)) => write!(f, " [range: {}..{}]", min, max)?,
+            (Some(min), None) => write!(f, " [min: {}]", min)?,
+            (None, Some(max)) => write!(f, " [max: {}]", max)?,
+            (None, None) => {}
+        }
+        for choice in &self.choices {
+            write!(f, "\n      {}", choice.value)?;
+            if let Some(name) = &choice.name {
+                write!(f, " = {}", name)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl OptionSchema {
+    // Checks that a value is well-formed for this option, the way Wayfire would
+    // parse it in `set-config-options`.
+    pub fn validate(&self, value: &str) -> Result<(), ParseError> {
+        let invalid = || ParseError(format!("invalid {} value: {}", self.option_type, value));
+
+        match self.option_type {
+            OptionType::Bool => match value.trim() {
+                "true" | "false" | "1" | "0" => Ok(()),
+                _ => Err(invalid()),
+            },
+            OptionType::Int => {
+                let number: i64 = value.trim().parse().map_err(|_| invalid())?;
+                if !self.choices.is_empty() && !self.choices.iter().any(|c| c.value == value.trim())
+                {
+                    return Err(invalid());
+                }
+                self.check_range(number as f64)
+            }
+            OptionType::Double => {
+                let number: f64 = value.trim().parse().map_err(|_| invalid())?;
+                self.check_range(number)
+            }
+            OptionType::Color => value.parse::<Color>().map(|_| ()),
+            OptionType::Key => value.parse::<KeyBinding>().map(|_| ()),
+            OptionType::Button => value.parse::<ButtonBinding>().map(|_| ()),
+            OptionType::Activator => value.parse::<Activator>().map(|_| ()),
+            OptionType::Animation => value.parse::<AnimationDuration>().map(|_| ()),
+            OptionType::OutputMode => value.parse::<OutputMode>().map(|_| ()),
+            OptionType::OutputPosition => value.parse::<OutputPosition>().map(|_| ()),
+            _ => Ok(()),
+        }
+    }
+
+    fn check_range(&self, value: f64) -> Result<(), ParseError> {
+        if self.min.is_some_and(|min| value < min) || self.max.is_some_and(|max| value > max) {
+            return Err(ParseError(format!(
+                "{} is out of range for {}",
+                value, self.name
+            )));
+        }
+        Ok(())
+    }
+}
+
+fn parse_metadata(xml: &str) -> Result<Vec<PluginSchema>, ParseError> {
+    let document =
+        roxmltree::Document::parse(xml).map_err(|e| ParseError(format!("invalid XML: {}", e)))?;
+
+    Ok(document
+        .root_element()
+        .children()
+        .filter(|node| node.has_tag_name("plugin") || node.has_tag_name("object"))
+        .map(parse_plugin)
+        .collect())
+}
+
+fn parse_plugin(node: roxmltree::Node) -> PluginSchema {
+    let mut plugin = PluginSchema {
+        name: node.attribute("name").unwrap_or_default().to_string(),
+        short: child_text(node, "_short"),
+        long: child_text(node, "_long"),
+        category: child_text(node, "category"),
+        options: Vec::new(),
+    };
+
+    collect_options(node, None, None, &mut plugin.options);
+    plugin
+}
+
+fn collect_options(
+    node: roxmltree::Node,
+    group: Option<&str>,
+    subgroup: Option<&str>,
+    options: &mut Vec<OptionSchema>,
+) {
+    for child in node.children().filter(|child| child.is_element()) {
+        match child.tag_name().name() {
+            "option" => options.push(parse_option(child, group, subgroup)),
+            "group" => {
+                let name = child_text(child, "_short");
+                collect_options(child, name.as_deref(), None, options);
+            }
+            "subgroup" => {
+                let name = child_text(child, "_short");
+                collect_options(child, group, name.as_deref(), options);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn parse_option(
+    node: roxmltree::Node,
+    group: Option<&str>,
+    subgroup: Option<&str>,
+) -> OptionSchema {
+    let number = |tag: &str| child_text(node, tag).and_then(|text| text.trim().parse().ok());
+
+    let choices = node
+        .children()
+        .filter(|child| child.has_tag_name("desc"))
+        .filter_map(|desc| {
+            Some(OptionChoice {
+                value: child_text(desc, "value")?,
+                name: child_text(desc, "_name"),
+            })
+        })
+        .collect();
+
+    OptionSchema {
+        name: node.attribute("name").unwrap_or_default().to_string(),
+        option_type: OptionType::from(node.attribute("type").unwrap_or("string").to_string()),
+        short: child_text(node, "_short"),
+        long: child_text(node, "_long"),
+        // An empty <default/> is a valid default (e.g. no binding).
+        default: node
+            .children()
+            .find(|child| child.has_tag_name("default"))
+            .map(|default| default.text().unwrap_or_default().trim().to_string()),
+        min: number("min"),
+        max: number("max"),
+        precision: number("precision"),
+        choices,
+        group: group.map(str::to_string),
+        subgroup: subgroup.map(str::to_string),
+    }
+}
+
+fn child_text(node: roxmltree::Node, tag: &str) -> Option<String> {
+    node.children()
+        .find(|child| child.has_tag_name(tag))
+        .and_then(|child| child.text())
+        .map(|text| text.trim().to_string())
+}
@@ -0,0 +1,210 @@
+use crate::ipc::WayfireSocket;
+use crate::models::{Event, Geometry, Output, View, WorkspacePosition};
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+// Drop-down windows: a scratchpad view is recalled centered over the focused
+// output's work area, kept above other windows, and hidden again on the next
+// toggle. If no view with the app-id exists, the app is started first.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Scratchpad {
+    pub app_id: String,
+    // Shell command starting the app, run through `sh -c`.
+    #[serde(default)]
+    pub command: Option<String>,
+    // Size relative to the work area.
+    #[serde(default = "default_ratio")]
+    pub width_ratio: f64,
+    #[serde(default = "default_ratio")]
+    pub height_ratio: f64,
+    #[serde(default)]
+    pub hide: HideMethod,
+}
+
+fn default_ratio() -> f64 {
+    0.6
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum HideMethod {
+    #[default]
+    Minimize,
+    // Moves the view to a workspace that isn't otherwise used.
+    Workspace(WorkspacePosition),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScratchpadState {
+    Shown(i64),
+    Hidden(i64),
+}
+
+const SPAWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+impl Scratchpad {
+    pub fn new(app_id: &str) -> Self {
+        Scratchpad {
+            app_id: app_id.to_string(),
+            command: None,
+            width_ratio: default_ratio(),
+            height_ratio: default_ratio(),
+            hide: HideMethod::default(),
+        }
+    }
+
+    pub fn command(mut self, command: &str) -> Self {
+        self.command = Some(command.to_string());
+        self
+    }
+
+    pub fn size(mut self, width_ratio: f64, height_ratio: f64) -> Self {
+        self.width_ratio = width_ratio;
+        self.height_ratio = height_ratio;
+        self
+    }
+
+    pub fn hide_with(mut self, hide: HideMethod) -> Self {
+        self.hide = hide;
+        self
+    }
+
+    pub async fn find_view(&self, socket: &mut WayfireSocket) -> io::Result<Option<View>> {
+        let views = socket.list_views().await?;
+        Ok(views
+            .into_iter()
+            .find(|view| view.mapped && view.is_toplevel() && view.app_id == self.app_id))
+    }
+
+    // The geometry of the recalled view on the given output.
+    pub fn geometry(&self, output: &Output) -> Geometry {
+        let area = output.work_area;
+        let width = (area.width as f64 * self.width_ratio.clamp(0.1, 1.0)) as i64;
+        let height = (area.height as f64 * self.height_ratio.clamp(0.1, 1.0)) as i64;
+
+        Geometry {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + (area.height - height) / 2,
+            width,
+            height,
+        }
+    }
+
+    // Shows the view centered on the focused output's current workspace.
+    pub async fn show(&self, socket: &mut WayfireSocket, view: &View) -> io::Result<()> {
+        let output = focused_output(socket).await?;
+        let geometry = self.geometry(&output);
+
+        if view.minimized {
+            socket.set_view_minimized(view.id, false).await?;
+        }
+        socket
+            .configure_view(
+                view.id,
+                geometry.x,
+                geometry.y,
+                geometry.width,
+                geometry.height,
+                Some(output.id),
+            )
+            .await?;
+        socket.set_view_always_on_top(view.id, true).await?;
+        socket.set_focus(view.id).await?;
+        Ok(())
+    }
+
+    pub async fn hide(&self, socket: &mut WayfireSocket, view: &View) -> io::Result<()> {
+        match self.hide {
+            HideMethod::Minimize => {
+                socket.set_view_minimized(view.id, true).await?;
+            }
+            HideMethod::Workspace(workspace) => {
+                socket
+                    .send_view_to_workspace(view.id, workspace.x, workspace.y)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    // Whether the view is currently shown on the focused output.
+    pub async fn is_shown(&self, socket: &mut WayfireSocket, view: &View) -> io::Result<bool> {
+        let output = focused_output(socket).await?;
+        Ok(!view.minimized
+            && view.output_id == output.id
+            && view.workspace(&output) == output.workspace.position())
+    }
+
+    // Hides the scratchpad if it is shown, and shows it otherwise, starting the
+    // app if no matching view exists yet. Starting the app subscribes the
+    // socket to `view-mapped` events.
+    pub async fn toggle(&self, socket: &mut WayfireSocket) -> io::Result<ScratchpadState> {
+        let Some(view) = self.find_view(socket).await? else {
+            let view = self.spawn(socket).await?;
+            self.show(socket, &view).await?;
+            return Ok(ScratchpadState::Shown(view.id));
+        };
+
+        if self.is_shown(socket, &view).await? {
+            self.hide(socket, &view).await?;
+            Ok(ScratchpadState::Hidden(view.id))
+        } else {
+            self.show(socket, &view).await?;
+            Ok(ScratchpadState::Shown(view.id))
+        }
+    }
+
+    // Starts the app and waits for its view to be mapped.
+    async fn spawn(&self, socket: &mut WayfireSocket) -> io::Result<View> {
+        let command = self.command.as_deref().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!(
+                    "No view with app-id {} and no command to start it",
+                    self.app_id
+                ),
+            )
+        })?;
+
+        socket.watch(Some(vec!["view-mapped".to_string()])).await?;
+
+        Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+
+        let deadline = Instant::now() + SPAWN_TIMEOUT;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match socket.read_event_timeout(remaining).await? {
+                Some(Event::ViewMapped(view))
+                    if view.is_toplevel() && view.app_id == self.app_id =>
+                {
+                    return Ok(view);
+                }
+                Some(_) => {}
+                None => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::TimedOut,
+                        format!(
+                            "{} did not open a view with app-id {}",
+                            command, self.app_id
+                        ),
+                    ))
+                }
+            }
+        }
+    }
+}
+
+async fn focused_output(socket: &mut WayfireSocket) -> io::Result<Output> {
+    socket
+        .get_focused_output()
+        .await
+        .map_err(|e| io::Error::other(e.to_string()))
+}
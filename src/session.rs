@@ -0,0 +1,278 @@
+use crate::ipc::WayfireSocket;
+use crate::models::{Event, Geometry, Output, View, ViewType, WorkspacePosition};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+// Window placement saved across restarts. Views are matched back to their
+// saved entry by app-id, preferring entries with the same title and output.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Session {
+    pub views: Vec<SavedView>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SavedView {
+    pub app_id: String,
+    pub title: String,
+    // Relative to the view's workspace.
+    pub geometry: Geometry,
+    pub output_name: String,
+    pub wset_index: i64,
+    pub workspace: WorkspacePosition,
+    #[serde(default)]
+    pub sticky: bool,
+    #[serde(default)]
+    pub fullscreen: bool,
+    #[serde(default)]
+    pub minimized: bool,
+    // Only saved when the alpha plugin is available.
+    #[serde(default)]
+    pub alpha: Option<f64>,
+}
+
+#[derive(Debug, Default)]
+pub struct RestoreSummary {
+    // Pairs of saved entry index and the view it was restored to.
+    pub restored: Vec<(usize, i64)>,
+    // Views matched to a saved entry that couldn't be fully put back.
+    pub failed: Vec<RestoreFailure>,
+    // Saved views no matching view was mapped for before the timeout.
+    pub missing: Vec<SavedView>,
+}
+
+// A view whose placement was only partly restored. Every step is attempted,
+// `errors` holds the ones that failed.
+#[derive(Debug)]
+pub struct RestoreFailure {
+    pub index: usize,
+    pub view_id: i64,
+    pub errors: Vec<io::Error>,
+}
+
+impl Session {
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        serde_json::from_str(&contents).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Failed to parse session: {}", e),
+            )
+        })
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+
+    // Index of the best unclaimed entry for a view: the app-id must match,
+    // a matching title or output breaks ties.
+    fn find_entry(&self, view: &View, claimed: &[usize]) -> Option<usize> {
+        self.views
+            .iter()
+            .enumerate()
+            .filter(|(index, saved)| !claimed.contains(index) && saved.app_id == view.app_id)
+            .max_by_key(|(index, saved)| {
+                let score = 2 * (saved.title == view.title) as usize
+                    + (saved.output_name == view.output_name) as usize;
+                // Earlier entries win among equal scores.
+                (score, usize::MAX - index)
+            })
+            .map(|(index, _)| index)
+    }
+}
+
+fn is_saved(view: &View) -> bool {
+    view.mapped && view.is_toplevel() && view.type_field == ViewType::Toplevel
+}
+
+pub async fn save_session(socket: &mut WayfireSocket) -> io::Result<Session> {
+    let views = socket.list_views().await?;
+    let outputs = socket.list_outputs().await?;
+    let has_alpha = socket.capabilities().await?.is_plugin_available("alpha");
+
+    let mut session = Session::default();
+    for view in views.iter().filter(|view| is_saved(view)) {
+        let Some(output) = outputs.iter().find(|o| o.id == view.output_id) else {
+            continue;
+        };
+
+        let alpha = if has_alpha {
+            Some(socket.get_view_alpha(view.id).await?.alpha)
+        } else {
+            None
+        };
+
+        session.views.push(SavedView {
+            app_id: view.app_id.clone(),
+            title: view.title.clone(),
+            geometry: view.workspace_geometry(output),
+            output_name: output.name.clone(),
+            wset_index: view.wset_index,
+            workspace: view.workspace(output),
+            sticky: view.sticky,
+            fullscreen: view.fullscreen,
+            minimized: view.minimized,
+            alpha,
+        });
+    }
+
+    Ok(session)
+}
+
+// Puts views back where they were saved. Views that are already open are
+// restored right away, the rest as they are mapped until `timeout` passes.
+// This subscribes the socket to `view-mapped` events, so use a connection
+// dedicated to the restore.
+pub async fn restore_session(
+    socket: &mut WayfireSocket,
+    session: &Session,
+    timeout: Duration,
+) -> io::Result<RestoreSummary> {
+    socket.watch(Some(vec!["view-mapped".to_string()])).await?;
+
+    let mut summary = RestoreSummary::default();
+    let mut claimed = Vec::new();
+
+    for view in socket.list_views().await? {
+        restore_view(socket, session, &view, &mut claimed, &mut summary).await;
+    }
+
+    let deadline = Instant::now() + timeout;
+    while claimed.len() < session.views.len() {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            break;
+        }
+
+        match socket.read_event_timeout(remaining).await? {
+            Some(Event::ViewMapped(view)) => {
+                restore_view(socket, session, &view, &mut claimed, &mut summary).await;
+            }
+            Some(_) => {}
+            None => break,
+        }
+    }
+
+    summary.missing = session
+        .views
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| !claimed.contains(index))
+        .map(|(_, saved)| saved.clone())
+        .collect();
+
+    Ok(summary)
+}
+
+// Restores a view if it matches an unclaimed entry. A failing step doesn't
+// stop the others; the view is reported in `summary.failed` instead.
+async fn restore_view(
+    socket: &mut WayfireSocket,
+    session: &Session,
+    view: &View,
+    claimed: &mut Vec<usize>,
+    summary: &mut RestoreSummary,
+) {
+    if !is_saved(view) {
+        return;
+    }
+    let Some(index) = session.find_entry(view, claimed) else {
+        return;
+    };
+    claimed.push(index);
+
+    let errors = place_view(socket, &session.views[index], view).await;
+    if errors.is_empty() {
+        summary.restored.push((index, view.id));
+    } else {
+        summary.failed.push(RestoreFailure {
+            index,
+            view_id: view.id,
+            errors,
+        });
+    }
+}
+
+async fn place_view(socket: &mut WayfireSocket, saved: &SavedView, view: &View) -> Vec<io::Error> {
+    let mut errors = Vec::new();
+
+    let outputs = match socket.list_outputs().await {
+        Ok(outputs) => outputs,
+        Err(e) => return vec![e],
+    };
+    let Some(mut output) = target_output(saved, &outputs).cloned() else {
+        return vec![io::Error::new(
+            io::ErrorKind::NotFound,
+            "No output to restore the view to",
+        )];
+    };
+
+    // Views always go to the workspace set the output shows, so the output
+    // is switched to the saved set first. Without the wsets plugin every
+    // output keeps its set.
+    if output.wset_index != saved.wset_index {
+        match socket.set_output_wset(output.id, saved.wset_index).await {
+            Ok(_) => match socket.get_output(output.id).await {
+                Ok(updated) => output = updated,
+                Err(e) => errors.push(e),
+            },
+            Err(e) if e.kind() == io::ErrorKind::Unsupported => {}
+            Err(e) => errors.push(e),
+        }
+    }
+
+    // The view is placed on the output's current workspace first and then
+    // sent to its saved workspace, which keeps its position within it.
+    let placed = socket
+        .configure_view(
+            view.id,
+            saved.geometry.x,
+            saved.geometry.y,
+            saved.geometry.width,
+            saved.geometry.height,
+            Some(output.id),
+        )
+        .await;
+    errors.extend(placed.err());
+
+    if saved.workspace != output.workspace.position() && !saved.sticky {
+        let sent = socket
+            .send_view_to_workspace(view.id, saved.workspace.x, saved.workspace.y)
+            .await;
+        errors.extend(sent.err());
+    }
+
+    if saved.sticky != view.sticky {
+        errors.extend(socket.set_view_sticky(view.id, saved.sticky).await.err());
+    }
+    if saved.fullscreen != view.fullscreen {
+        let result = socket.set_view_fullscreen(view.id, saved.fullscreen).await;
+        errors.extend(result.err());
+    }
+    if let Some(alpha) = saved.alpha {
+        errors.extend(socket.set_view_alpha(view.id, alpha).await.err());
+    }
+    if saved.minimized != view.minimized {
+        let result = socket.set_view_minimized(view.id, saved.minimized).await;
+        errors.extend(result.err());
+    }
+
+    errors
+}
+
+// The output the view was on or, if it's gone, the one now showing the
+// view's workspace set, falling back to the first output.
+fn target_output<'a>(saved: &SavedView, outputs: &'a [Output]) -> Option<&'a Output> {
+    outputs
+        .iter()
+        .find(|o| o.name == saved.output_name)
+        .or_else(|| outputs.iter().find(|o| o.wset_index == saved.wset_index))
+        .or_else(|| outputs.first())
+}
@@ -0,0 +1,232 @@
+use crate::ipc::WayfireSocket;
+use crate::models::{Event, View};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+
+// Window swallowing: a toplevel opened by a process started from a terminal
+// takes the terminal's place, and the terminal is minimized until every view
+// that swallowed it is unmapped again. Processes are matched to terminals by
+// walking the parent chain in /proc, so this only works for local clients.
+#[derive(Debug, Clone)]
+pub struct Swallower {
+    terminals: Vec<String>,
+    exclude: Vec<String>,
+    // Swallowing view id to terminal view id.
+    swallowed: HashMap<i64, i64>,
+}
+
+// Terminals that serve every window from one process (`foot --server` with
+// footclient, `kitty --single-instance`, wezterm) can't be told apart by pid.
+// When several of their windows match, the one on the view's output that was
+// focused last is taken, which is usually but not always where the command
+// was started.
+pub const DEFAULT_TERMINALS: [&str; 7] = [
+    "foot",
+    "footclient",
+    "kitty",
+    "Alacritty",
+    "org.wezfurlong.wezterm",
+    "com.mitchellh.ghostty",
+    "org.gnome.Console",
+];
+
+pub const SWALLOW_EVENTS: [&str; 2] = ["view-mapped", "view-unmapped"];
+
+// Deeper chains are assumed to be loops or unrelated processes.
+const MAX_ANCESTRY_DEPTH: usize = 64;
+
+// The parent process id from /proc/PID/stat.
+pub fn parent_pid(pid: i64) -> Option<i64> {
+    let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    // The command name may contain spaces and parentheses, the fields after
+    // it are "state ppid ...".
+    let (_, fields) = stat.rsplit_once(')')?;
+    fields.split_whitespace().nth(1)?.parse().ok()
+}
+
+// The parent, grandparent and so on of a process, up to but excluding init.
+pub fn ancestors(pid: i64) -> Vec<i64> {
+    let mut ancestors = Vec::new();
+    let mut current = pid;
+    while ancestors.len() < MAX_ANCESTRY_DEPTH {
+        match parent_pid(current) {
+            Some(parent) if parent > 1 => {
+                ancestors.push(parent);
+                current = parent;
+            }
+            _ => break,
+        }
+    }
+    ancestors
+}
+
+pub fn is_descendant(pid: i64, ancestor: i64) -> bool {
+    ancestors(pid).contains(&ancestor)
+}
+
+// What `Swallower::run` did, for reporting.
+#[derive(Debug)]
+pub enum SwallowEvent {
+    Swallowed { view: View, terminal: i64 },
+    Restored(i64),
+    SwallowFailed { view: View, error: io::Error },
+    RestoreFailed { view: View, error: io::Error },
+}
+
+impl Default for Swallower {
+    fn default() -> Self {
+        Swallower {
+            terminals: DEFAULT_TERMINALS.iter().map(|t| t.to_string()).collect(),
+            exclude: Vec::new(),
+            swallowed: HashMap::new(),
+        }
+    }
+}
+
+impl Swallower {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // App-ids of the terminals that can be swallowed, replacing the defaults.
+    pub fn terminals(mut self, app_ids: Vec<String>) -> Self {
+        self.terminals = app_ids;
+        self
+    }
+
+    // App-ids that never swallow their terminal.
+    pub fn exclude(mut self, app_id: &str) -> Self {
+        self.exclude.push(app_id.to_string());
+        self
+    }
+
+    pub fn swallowed(&self) -> &HashMap<i64, i64> {
+        &self.swallowed
+    }
+
+    fn is_terminal(&self, view: &View) -> bool {
+        self.terminals.contains(&view.app_id)
+    }
+
+    // Swallows the terminal the view was started from, if any, and returns
+    // the terminal's id. Dialogs, terminals and excluded apps don't swallow.
+    pub async fn swallow(
+        &mut self,
+        socket: &mut WayfireSocket,
+        view: &View,
+    ) -> io::Result<Option<i64>> {
+        if !view.is_toplevel()
+            || view.parent >= 0
+            || view.pid <= 0
+            || self.is_terminal(view)
+            || self.exclude.contains(&view.app_id)
+        {
+            return Ok(None);
+        }
+
+        // The closest terminal ancestor wins; windows sharing its process
+        // are told apart as described at `DEFAULT_TERMINALS`.
+        let ancestors = ancestors(view.pid);
+        let views = socket.list_views().await?;
+        let Some(terminal) = views
+            .iter()
+            .filter(|candidate| {
+                candidate.id != view.id
+                    && candidate.mapped
+                    && candidate.pid > 0
+                    && self.is_terminal(candidate)
+            })
+            .filter_map(|candidate| {
+                let depth = ancestors.iter().position(|pid| *pid == candidate.pid)?;
+                Some((candidate, depth))
+            })
+            .max_by_key(|(candidate, depth)| {
+                (
+                    Reverse(*depth),
+                    candidate.output_id == view.output_id,
+                    candidate.last_focus_timestamp,
+                )
+            })
+            .map(|(candidate, _)| candidate)
+        else {
+            return Ok(None);
+        };
+
+        // A terminal already swallowed by another view keeps its place
+        // there, the new view is only recorded.
+        let already_swallowed = self.swallowed.values().any(|id| *id == terminal.id);
+        self.swallowed.insert(view.id, terminal.id);
+        if already_swallowed {
+            return Ok(Some(terminal.id));
+        }
+
+        let geometry = terminal.geometry;
+        socket
+            .configure_view(
+                view.id,
+                geometry.x,
+                geometry.y,
+                geometry.width,
+                geometry.height,
+                Some(terminal.output_id),
+            )
+            .await?;
+        socket.set_view_minimized(terminal.id, true).await?;
+        socket.set_focus(view.id).await?;
+
+        Ok(Some(terminal.id))
+    }
+
+    // Handles an unmapped view. Returns the id of the terminal restored
+    // because its last swallowing view went away.
+    pub async fn release(
+        &mut self,
+        socket: &mut WayfireSocket,
+        view_id: i64,
+    ) -> io::Result<Option<i64>> {
+        // A closed terminal has nothing left to restore.
+        self.swallowed.retain(|_, terminal| *terminal != view_id);
+
+        let Some(terminal) = self.swallowed.remove(&view_id) else {
+            return Ok(None);
+        };
+        if self.swallowed.values().any(|id| *id == terminal) {
+            return Ok(None);
+        }
+
+        socket.set_view_minimized(terminal, false).await?;
+        socket.set_focus(terminal).await?;
+        Ok(Some(terminal))
+    }
+
+    // Swallows terminals as views are mapped and restores them as views are
+    // unmapped, reporting what was done to `on_event`. Failing actions don't
+    // stop the daemon.
+    pub async fn run(
+        &mut self,
+        socket: &mut WayfireSocket,
+        mut on_event: impl FnMut(SwallowEvent),
+    ) -> io::Result<()> {
+        socket
+            .watch(Some(SWALLOW_EVENTS.iter().map(|e| e.to_string()).collect()))
+            .await?;
+
+        loop {
+            match socket.read_event().await? {
+                Event::ViewMapped(view) => match self.swallow(socket, &view).await {
+                    Ok(Some(terminal)) => on_event(SwallowEvent::Swallowed { view, terminal }),
+                    Ok(None) => {}
+                    Err(error) => on_event(SwallowEvent::SwallowFailed { view, error }),
+                },
+                Event::ViewUnmapped(view) => match self.release(socket, view.id).await {
+                    Ok(Some(terminal)) => on_event(SwallowEvent::Restored(terminal)),
+                    Ok(None) => {}
+                    Err(error) => on_event(SwallowEvent::RestoreFailed { view, error }),
+                },
+                _ => {}
+            }
+        }
+    }
+}
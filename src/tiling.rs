@@ -0,0 +1,694 @@
+use crate::ipc::WayfireSocket;
+use crate::models::{Event, Geometry, Output, ParseError, View, ViewLayer, ViewType};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+
+// Client-side auto-tiling: toplevel views are tracked per workspace and placed
+// with `configure_view` according to that workspace's layout. Dialogs (views
+// with a parent), sticky, minimized and fullscreen views are left floating.
+
+// Arranges `count` windows inside `area`. Tiles are returned in the order of
+// the workspace's view list, the first view being the "master".
+pub trait TilingLayout {
+    fn name(&self) -> &str;
+    fn arrange(&self, area: Geometry, count: usize) -> Vec<Geometry>;
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[serde(try_from = "String", into = "String")]
+pub enum LayoutKind {
+    #[default]
+    MasterStack,
+    Dwindle,
+    Columns,
+    Monocle,
+    Grid,
+}
+
+impl LayoutKind {
+    pub const ALL: [LayoutKind; 5] = [
+        LayoutKind::MasterStack,
+        LayoutKind::Dwindle,
+        LayoutKind::Columns,
+        LayoutKind::Monocle,
+        LayoutKind::Grid,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LayoutKind::MasterStack => "master-stack",
+            LayoutKind::Dwindle => "dwindle",
+            LayoutKind::Columns => "columns",
+            LayoutKind::Monocle => "monocle",
+            LayoutKind::Grid => "grid",
+        }
+    }
+
+    pub fn layout(&self) -> Box<dyn TilingLayout> {
+        match self {
+            LayoutKind::MasterStack => Box::new(MasterStack::default()),
+            LayoutKind::Dwindle => Box::new(Dwindle),
+            LayoutKind::Columns => Box::new(Columns),
+            LayoutKind::Monocle => Box::new(Monocle),
+            LayoutKind::Grid => Box::new(GridLayout),
+        }
+    }
+
+    // The layout after this one, for cycling through the built-in layouts.
+    pub fn next(&self) -> LayoutKind {
+        let index = LayoutKind::ALL.iter().position(|k| k == self).unwrap_or(0);
+        LayoutKind::ALL[(index + 1) % LayoutKind::ALL.len()]
+    }
+}
+
+impl fmt::Display for LayoutKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for LayoutKind {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "master-stack" | "tall" => Ok(LayoutKind::MasterStack),
+            "dwindle" | "spiral" => Ok(LayoutKind::Dwindle),
+            "columns" => Ok(LayoutKind::Columns),
+            "monocle" => Ok(LayoutKind::Monocle),
+            "grid" => Ok(LayoutKind::Grid),
+            _ => Err(ParseError(format!("unknown layout: {}", s))),
+        }
+    }
+}
+
+impl TryFrom<String> for LayoutKind {
+    type Error = ParseError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<LayoutKind> for String {
+    fn from(value: LayoutKind) -> Self {
+        value.to_string()
+    }
+}
+
+// One or more master windows on the left, the rest stacked on the right.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MasterStack {
+    pub master_ratio: f64,
+    pub master_count: usize,
+}
+
+impl Default for MasterStack {
+    fn default() -> Self {
+        MasterStack {
+            master_ratio: 0.55,
+            master_count: 1,
+        }
+    }
+}
+
+impl TilingLayout for MasterStack {
+    fn name(&self) -> &str {
+        LayoutKind::MasterStack.as_str()
+    }
+
+    fn arrange(&self, area: Geometry, count: usize) -> Vec<Geometry> {
+        let masters = self.master_count.max(1);
+        if count <= masters {
+            return split_rows(area, count);
+        }
+
+        let master_width = (area.width as f64 * self.master_ratio.clamp(0.1, 0.9)) as i64;
+        let (master_area, stack_area) = split_at_x(area, master_width);
+
+        let mut tiles = split_rows(master_area, masters);
+        tiles.extend(split_rows(stack_area, count - masters));
+        tiles
+    }
+}
+
+// Each window takes half of the space left by the previous one, alternating
+// between vertical and horizontal splits.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Dwindle;
+
+impl TilingLayout for Dwindle {
+    fn name(&self) -> &str {
+        LayoutKind::Dwindle.as_str()
+    }
+
+    fn arrange(&self, area: Geometry, count: usize) -> Vec<Geometry> {
+        let mut tiles = Vec::with_capacity(count);
+        let mut remaining = area;
+
+        for index in 0..count {
+            if index + 1 == count {
+                tiles.push(remaining);
+                break;
+            }
+
+            let (tile, rest) = if index % 2 == 0 {
+                split_at_x(remaining, remaining.width / 2)
+            } else {
+                split_at_y(remaining, remaining.height / 2)
+            };
+            tiles.push(tile);
+            remaining = rest;
+        }
+
+        tiles
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Columns;
+
+impl TilingLayout for Columns {
+    fn name(&self) -> &str {
+        LayoutKind::Columns.as_str()
+    }
+
+    fn arrange(&self, area: Geometry, count: usize) -> Vec<Geometry> {
+        split_columns(area, count)
+    }
+}
+
+// Every window fills the whole area, stacked on top of each other.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Monocle;
+
+impl TilingLayout for Monocle {
+    fn name(&self) -> &str {
+        LayoutKind::Monocle.as_str()
+    }
+
+    fn arrange(&self, area: Geometry, count: usize) -> Vec<Geometry> {
+        vec![area; count]
+    }
+}
+
+// Rows of equally sized windows; the last row is stretched if it isn't full.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct GridLayout;
+
+impl TilingLayout for GridLayout {
+    fn name(&self) -> &str {
+        LayoutKind::Grid.as_str()
+    }
+
+    fn arrange(&self, area: Geometry, count: usize) -> Vec<Geometry> {
+        if count == 0 {
+            return Vec::new();
+        }
+
+        let columns = (count as f64).sqrt().ceil() as usize;
+        let rows = count.div_ceil(columns);
+
+        split_rows(area, rows)
+            .into_iter()
+            .enumerate()
+            .flat_map(|(row, row_area)| {
+                let in_row = columns.min(count - row * columns);
+                split_columns(row_area, in_row)
+            })
+            .collect()
+    }
+}
+
+fn split_at_x(area: Geometry, width: i64) -> (Geometry, Geometry) {
+    let left = Geometry { width, ..area };
+    let right = Geometry {
+        x: area.x + width,
+        width: area.width - width,
+        ..area
+    };
+    (left, right)
+}
+
+fn split_at_y(area: Geometry, height: i64) -> (Geometry, Geometry) {
+    let top = Geometry { height, ..area };
+    let bottom = Geometry {
+        y: area.y + height,
+        height: area.height - height,
+        ..area
+    };
+    (top, bottom)
+}
+
+// Splits a length into `count` parts whose edges line up exactly.
+fn split_length(start: i64, length: i64, count: usize) -> Vec<(i64, i64)> {
+    let count = count as i64;
+    (0..count)
+        .map(|i| {
+            let from = start + length * i / count;
+            let to = start + length * (i + 1) / count;
+            (from, to - from)
+        })
+        .collect()
+}
+
+fn split_columns(area: Geometry, count: usize) -> Vec<Geometry> {
+    split_length(area.x, area.width, count)
+        .into_iter()
+        .map(|(x, width)| Geometry { x, width, ..area })
+        .collect()
+}
+
+fn split_rows(area: Geometry, count: usize) -> Vec<Geometry> {
+    split_length(area.y, area.height, count)
+        .into_iter()
+        .map(|(y, height)| Geometry { y, height, ..area })
+        .collect()
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Gaps {
+    // Space between neighbouring windows.
+    pub inner: i64,
+    // Space between windows and the edges of the work area.
+    pub outer: i64,
+}
+
+impl Gaps {
+    pub fn new(inner: i64, outer: i64) -> Self {
+        Gaps { inner, outer }
+    }
+
+    // The area to arrange tiles in: half of the inner gap is added back to the
+    // outer edges, since every tile is later shrunk by half of it.
+    fn layout_area(&self, work_area: Geometry) -> Geometry {
+        shrink(work_area, self.outer - self.inner / 2)
+    }
+
+    fn tile(&self, tile: Geometry) -> Geometry {
+        shrink(tile, self.inner / 2)
+    }
+}
+
+fn shrink(area: Geometry, by: i64) -> Geometry {
+    Geometry {
+        x: area.x + by,
+        y: area.y + by,
+        width: (area.width - 2 * by).max(1),
+        height: (area.height - 2 * by).max(1),
+    }
+}
+
+// Workspaces are identified by their workspace set and grid position, so
+// layouts stay with a wset when it moves to another output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WorkspaceKey {
+    pub wset: i64,
+    pub x: i64,
+    pub y: i64,
+}
+
+impl fmt::Display for WorkspaceKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "wset {} ({}, {})", self.wset, self.x, self.y)
+    }
+}
+
+struct WorkspaceState {
+    layout: Box<dyn TilingLayout>,
+    views: Vec<i64>,
+}
+
+pub struct Tiler {
+    default_layout: LayoutKind,
+    gaps: Gaps,
+    workspaces: HashMap<WorkspaceKey, WorkspaceState>,
+}
+
+const TILER_EVENTS: [&str; 9] = [
+    "view-mapped",
+    "view-unmapped",
+    "view-minimized",
+    "view-fullscreen",
+    "view-sticky",
+    "view-set-output",
+    "view-workspace-changed",
+    "view-wset-changed",
+    "output-removed",
+];
+
+impl Tiler {
+    pub fn new(default_layout: LayoutKind, gaps: Gaps) -> Self {
+        Tiler {
+            default_layout,
+            gaps,
+            workspaces: HashMap::new(),
+        }
+    }
+
+    pub fn gaps(&self) -> Gaps {
+        self.gaps
+    }
+
+    pub fn set_gaps(&mut self, gaps: Gaps) {
+        self.gaps = gaps;
+    }
+
+    pub fn is_tileable(view: &View) -> bool {
+        view.mapped
+            && view.is_toplevel()
+            && view.type_field == ViewType::Toplevel
+            && view.layer == ViewLayer::Workspace
+            && view.parent < 0
+            && !view.minimized
+            && !view.fullscreen
+            && !view.sticky
+    }
+
+    pub fn workspace_of(view: &View, output: &Output) -> WorkspaceKey {
+        let workspace = view.workspace(output);
+        WorkspaceKey {
+            wset: output.wset_index,
+            x: workspace.x,
+            y: workspace.y,
+        }
+    }
+
+    pub fn layout_name(&self, key: &WorkspaceKey) -> String {
+        match self.workspaces.get(key) {
+            Some(state) => state.layout.name().to_string(),
+            None => self.default_layout.to_string(),
+        }
+    }
+
+    // Tiled views of a workspace, master first.
+    pub fn views(&self, key: &WorkspaceKey) -> &[i64] {
+        self.workspaces
+            .get(key)
+            .map_or(&[], |state| state.views.as_slice())
+    }
+
+    pub fn set_layout(&mut self, key: WorkspaceKey, layout: Box<dyn TilingLayout>) {
+        self.state(key).layout = layout;
+    }
+
+    pub fn set_layout_kind(&mut self, key: WorkspaceKey, kind: LayoutKind) {
+        self.set_layout(key, kind.layout());
+    }
+
+    // Moves a view to the front of its workspace's list, making it the master.
+    pub fn promote(&mut self, view_id: i64) -> Option<WorkspaceKey> {
+        let (key, state) = self
+            .workspaces
+            .iter_mut()
+            .find(|(_, state)| state.views.contains(&view_id))?;
+
+        state.views.retain(|id| *id != view_id);
+        state.views.insert(0, view_id);
+        Some(*key)
+    }
+
+    // Swaps the positions of two views of the same workspace.
+    pub fn swap(&mut self, a: i64, b: i64) -> Option<WorkspaceKey> {
+        let (key, state) = self
+            .workspaces
+            .iter_mut()
+            .find(|(_, state)| state.views.contains(&a) && state.views.contains(&b))?;
+
+        let i = state.views.iter().position(|id| *id == a)?;
+        let j = state.views.iter().position(|id| *id == b)?;
+        state.views.swap(i, j);
+        Some(*key)
+    }
+
+    fn state(&mut self, key: WorkspaceKey) -> &mut WorkspaceState {
+        let default_layout = self.default_layout;
+        self.workspaces
+            .entry(key)
+            .or_insert_with(|| WorkspaceState {
+                layout: default_layout.layout(),
+                views: Vec::new(),
+            })
+    }
+
+    // Updates the per-workspace view lists from the compositor's state. Views
+    // keep their position in the list; new ones are appended.
+    pub fn update(&mut self, views: &[View], outputs: &[Output]) {
+        let mut current: HashMap<i64, WorkspaceKey> = HashMap::new();
+        let mut new_views = Vec::new();
+
+        for view in views.iter().filter(|view| Self::is_tileable(view)) {
+            let Some(output) = outputs.iter().find(|o| o.id == view.output_id) else {
+                continue;
+            };
+            let key = Self::workspace_of(view, output);
+            current.insert(view.id, key);
+
+            let known = self
+                .workspaces
+                .get(&key)
+                .is_some_and(|state| state.views.contains(&view.id));
+            if !known {
+                new_views.push((view.id, key));
+            }
+        }
+
+        for (key, state) in self.workspaces.iter_mut() {
+            state.views.retain(|id| current.get(id) == Some(key));
+        }
+
+        new_views.sort_by_key(|(view_id, _)| *view_id);
+        for (view_id, key) in new_views {
+            self.state(key).views.push(view_id);
+        }
+    }
+
+    // Computes the geometry of every tiled view of a workspace.
+    pub fn plan(&self, key: &WorkspaceKey, output: &Output) -> Vec<(i64, Geometry)> {
+        let Some(state) = self.workspaces.get(key) else {
+            return Vec::new();
+        };
+
+        // Workspaces other than the current one are laid out where they'd be
+        // relative to it.
+        let mut area = self.gaps.layout_area(output.work_area);
+        area.x += (key.x - output.workspace.x) * output.geometry.width;
+        area.y += (key.y - output.workspace.y) * output.geometry.height;
+
+        state
+            .views
+            .iter()
+            .copied()
+            .zip(state.layout.arrange(area, state.views.len()))
+            .map(|(view_id, tile)| (view_id, self.gaps.tile(tile)))
+            .collect()
+    }
+
+    // Places the views of every workspace shown on an output, skipping views
+    // that already have the planned geometry.
+    pub async fn arrange(&mut self, socket: &mut WayfireSocket) -> io::Result<()> {
+        let views = socket.list_views().await?;
+        let outputs = socket.list_outputs().await?;
+        self.update(&views, &outputs);
+
+        for output in &outputs {
+            let keys: Vec<WorkspaceKey> = self
+                .workspaces
+                .keys()
+                .filter(|key| key.wset == output.wset_index)
+                .copied()
+                .collect();
+
+            for key in keys {
+                for (view_id, tile) in self.plan(&key, output) {
+                    let Some(view) = views.iter().find(|v| v.id == view_id) else {
+                        continue;
+                    };
+                    let tile = respect_min_size(tile, view);
+                    if view.geometry == tile {
+                        continue;
+                    }
+
+                    socket
+                        .configure_view(
+                            view_id,
+                            tile.x,
+                            tile.y,
+                            tile.width,
+                            tile.height,
+                            Some(output.id),
+                        )
+                        .await?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // Watches view events and re-arranges the affected workspaces until the
+    // connection is closed.
+    pub async fn run(&mut self, socket: &mut WayfireSocket) -> io::Result<()> {
+        socket
+            .watch(Some(TILER_EVENTS.iter().map(|e| e.to_string()).collect()))
+            .await?;
+
+        self.arrange(socket).await?;
+
+        loop {
+            let relevant = match socket.read_event().await? {
+                Event::ViewMapped(_) | Event::ViewUnmapped(_) | Event::OutputRemoved(_) => true,
+                Event::Other(value) => value
+                    .get("event")
+                    .and_then(|e| e.as_str())
+                    .is_some_and(|e| TILER_EVENTS.contains(&e)),
+                _ => false,
+            };
+
+            if relevant {
+                self.arrange(socket).await?;
+            }
+        }
+    }
+}
+
+// Views that can't shrink to their tile overlap their neighbours instead of
+// being squeezed below their minimum size.
+fn respect_min_size(tile: Geometry, view: &View) -> Geometry {
+    Geometry {
+        width: tile.width.max(view.min_size.width),
+        height: tile.height.max(view.min_size.height),
+        ..tile
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn geometry(x: i64, y: i64, width: i64, height: i64) -> Geometry {
+        Geometry {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn split_length_edges_line_up() {
+        assert_eq!(split_length(0, 100, 3), [(0, 33), (33, 33), (66, 34)]);
+        assert_eq!(split_length(10, 5, 1), [(10, 5)]);
+        assert!(split_length(0, 100, 0).is_empty());
+    }
+
+    #[test]
+    fn master_stack_puts_the_first_view_left() {
+        let area = geometry(0, 0, 1000, 600);
+
+        assert_eq!(MasterStack::default().arrange(area, 1), [area]);
+        assert_eq!(
+            MasterStack::default().arrange(area, 3),
+            [
+                geometry(0, 0, 550, 600),
+                geometry(550, 0, 450, 300),
+                geometry(550, 300, 450, 300),
+            ]
+        );
+
+        let two_masters = MasterStack {
+            master_ratio: 0.5,
+            master_count: 2,
+        };
+        assert_eq!(
+            two_masters.arrange(area, 2),
+            [geometry(0, 0, 1000, 300), geometry(0, 300, 1000, 300)]
+        );
+        assert_eq!(
+            two_masters.arrange(area, 3),
+            [
+                geometry(0, 0, 500, 300),
+                geometry(0, 300, 500, 300),
+                geometry(500, 0, 500, 600),
+            ]
+        );
+    }
+
+    #[test]
+    fn dwindle_halves_the_remaining_space() {
+        let tiles = Dwindle.arrange(geometry(0, 0, 800, 600), 4);
+        assert_eq!(
+            tiles,
+            [
+                geometry(0, 0, 400, 600),
+                geometry(400, 0, 400, 300),
+                geometry(400, 300, 200, 300),
+                geometry(600, 300, 200, 300),
+            ]
+        );
+        assert!(Dwindle.arrange(geometry(0, 0, 800, 600), 0).is_empty());
+    }
+
+    #[test]
+    fn grid_stretches_the_last_row() {
+        let tiles = GridLayout.arrange(geometry(0, 0, 900, 600), 5);
+        assert_eq!(
+            tiles,
+            [
+                geometry(0, 0, 300, 300),
+                geometry(300, 0, 300, 300),
+                geometry(600, 0, 300, 300),
+                geometry(0, 300, 450, 300),
+                geometry(450, 300, 450, 300),
+            ]
+        );
+        assert!(GridLayout.arrange(geometry(0, 0, 900, 600), 0).is_empty());
+    }
+
+    #[test]
+    fn columns_and_monocle_cover_the_area() {
+        let area = geometry(10, 20, 300, 200);
+        assert_eq!(
+            Columns.arrange(area, 2),
+            [geometry(10, 20, 150, 200), geometry(160, 20, 150, 200)]
+        );
+        assert_eq!(Monocle.arrange(area, 2), [area, area]);
+    }
+
+    #[test]
+    fn gaps_separate_tiles_evenly() {
+        let gaps = Gaps::new(10, 20);
+        let area = gaps.layout_area(geometry(0, 0, 1000, 600));
+        assert_eq!(area, geometry(15, 15, 970, 570));
+
+        let tiles: Vec<Geometry> = Columns
+            .arrange(area, 2)
+            .into_iter()
+            .map(|tile| gaps.tile(tile))
+            .collect();
+        assert_eq!(
+            tiles,
+            [geometry(20, 20, 475, 560), geometry(505, 20, 475, 560)]
+        );
+        assert_eq!(shrink(geometry(0, 0, 4, 4), 5).width, 1);
+    }
+
+    #[test]
+    fn layout_kinds_parse_and_cycle() {
+        assert_eq!(
+            "tall".parse::<LayoutKind>().unwrap(),
+            LayoutKind::MasterStack
+        );
+        assert_eq!("spiral".parse::<LayoutKind>().unwrap(), LayoutKind::Dwindle);
+        assert!("stack".parse::<LayoutKind>().is_err());
+
+        for kind in LayoutKind::ALL {
+            assert_eq!(kind.as_str().parse::<LayoutKind>().unwrap(), kind);
+            assert_eq!(kind.layout().name(), kind.as_str());
+        }
+        assert_eq!(LayoutKind::Grid.next(), LayoutKind::MasterStack);
+    }
+}
@@ -0,0 +1,198 @@
+use crate::models::{Output, WorkspacePosition};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// Names, icons and free-form metadata for workspaces, which Wayfire itself
+// only knows as grid cells. Workspaces are keyed by output name, workspace
+// set and position:
+//
+//     [[workspace]]
+//     output = "eDP-1"
+//     wset = 1
+//     x = 0
+//     y = 0
+//     name = "web"
+//     icon = "firefox"
+//     metadata = { color = "#e66000" }
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct WorkspaceRegistry {
+    #[serde(rename = "workspace", alias = "workspaces", default)]
+    pub workspaces: Vec<WorkspaceEntry>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct WorkspaceId {
+    pub output: String,
+    pub wset: i64,
+    pub x: i64,
+    pub y: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct WorkspaceInfo {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct WorkspaceEntry {
+    #[serde(flatten)]
+    pub id: WorkspaceId,
+    #[serde(flatten)]
+    pub info: WorkspaceInfo,
+}
+
+impl WorkspaceId {
+    pub fn new(output: &Output, position: WorkspacePosition) -> Self {
+        WorkspaceId {
+            output: output.name.clone(),
+            wset: output.wset_index,
+            x: position.x,
+            y: position.y,
+        }
+    }
+
+    // The workspace the output currently shows.
+    pub fn current(output: &Output) -> Self {
+        Self::new(output, output.workspace.position())
+    }
+
+    pub fn position(&self) -> WorkspacePosition {
+        WorkspacePosition {
+            x: self.x,
+            y: self.y,
+        }
+    }
+
+    // The output showing the workspace. A workspace set moved to another
+    // output takes its workspaces along, so any output showing the set
+    // counts when the one it was named on doesn't.
+    pub fn find_output<'a>(&self, outputs: &'a [Output]) -> Option<&'a Output> {
+        outputs
+            .iter()
+            .find(|o| o.name == self.output && o.wset_index == self.wset)
+            .or_else(|| outputs.iter().find(|o| o.wset_index == self.wset))
+    }
+}
+
+impl fmt::Display for WorkspaceId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} wset {} ({}, {})",
+            self.output, self.wset, self.x, self.y
+        )
+    }
+}
+
+impl WorkspaceInfo {
+    pub fn is_empty(&self) -> bool {
+        self.name.is_none() && self.icon.is_none() && self.metadata.is_empty()
+    }
+}
+
+impl WorkspaceRegistry {
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        Self::parse(&contents)
+    }
+
+    // Like `load`, but a missing file is an empty registry.
+    pub fn load_or_default<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        match Self::load(path) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            result => result,
+        }
+    }
+
+    pub fn parse(contents: &str) -> io::Result<Self> {
+        toml::from_str(contents).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Failed to parse workspaces: {}", e),
+            )
+        })
+    }
+
+    // Saves the registry sorted by workspace, leaving out workspaces without
+    // a name, icon or metadata.
+    pub fn save<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
+        self.workspaces.retain(|entry| !entry.info.is_empty());
+        self.workspaces.sort_by(|a, b| a.id.cmp(&b.id));
+
+        let contents = toml::to_string(self).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Failed to serialize workspaces: {}", e),
+            )
+        })?;
+
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, contents)
+    }
+
+    pub fn get(&self, id: &WorkspaceId) -> Option<&WorkspaceInfo> {
+        self.workspaces
+            .iter()
+            .find(|entry| &entry.id == id)
+            .map(|entry| &entry.info)
+    }
+
+    // The info for the workspace, added empty if it isn't registered yet.
+    pub fn entry(&mut self, id: &WorkspaceId) -> &mut WorkspaceInfo {
+        let index = match self.workspaces.iter().position(|entry| &entry.id == id) {
+            Some(index) => index,
+            None => {
+                self.workspaces.push(WorkspaceEntry {
+                    id: id.clone(),
+                    info: WorkspaceInfo::default(),
+                });
+                self.workspaces.len() - 1
+            }
+        };
+        &mut self.workspaces[index].info
+    }
+
+    pub fn remove(&mut self, id: &WorkspaceId) -> Option<WorkspaceInfo> {
+        let index = self.workspaces.iter().position(|entry| &entry.id == id)?;
+        Some(self.workspaces.remove(index).info)
+    }
+
+    // The workspace with the given name. Names only need to be unique per
+    // output; a workspace on `preferred_output` wins over the others.
+    pub fn find(&self, name: &str, preferred_output: Option<&str>) -> Option<&WorkspaceId> {
+        let named = || {
+            self.workspaces
+                .iter()
+                .filter(|entry| entry.info.name.as_deref() == Some(name))
+        };
+
+        named()
+            .find(|entry| Some(entry.id.output.as_str()) == preferred_output)
+            .or_else(|| named().next())
+            .map(|entry| &entry.id)
+    }
+
+    // The current workspace of each output with its info, e.g. for a bar.
+    pub fn current(&self, outputs: &[Output]) -> Vec<WorkspaceEntry> {
+        outputs
+            .iter()
+            .map(|output| {
+                let id = WorkspaceId::current(output);
+                let info = self.get(&id).cloned().unwrap_or_default();
+                WorkspaceEntry { id, info }
+            })
+            .collect()
+    }
+}